@@ -0,0 +1,707 @@
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+
+use crate::emoji::{EmojiMap, RandomNumber};
+use crate::emoji_ui::{EmojiGuesses, EmojiTile, PlacementMenuBtn};
+use crate::game::GameState;
+use crate::score::{RoundResult, RoundScored, SlotResult};
+use crate::settings::GameSettings;
+
+/// Port the vote endpoint listens on when none is given.
+pub const DEFAULT_PORT: u16 = 7879;
+/// Number of emojis shown in the histogram of a single slot.
+const HISTOGRAM_ROWS: usize = 3;
+/// Largest request read from a voter.
+const MAX_REQUEST_LEN: usize = 4096;
+/// Seconds a voter has to send the whole request and read the response.
+const REQUEST_TIMEOUT: f32 = 2.0;
+/// Most voters counted in a single slot.
+const MAX_VOTERS: usize = 10_000;
+/// Longest voter id.
+const MAX_VOTER_LEN: usize = 64;
+/// Most requests answered from a single address each second, a chat bot may vote for many viewers.
+const MAX_REQUESTS_PER_SECOND: u32 = 50;
+/// Shortest time between two redraws of the histograms, votes can come in every frame.
+pub const HISTOGRAM_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Local HTTP endpoint the audience votes through, enabled with `--audience [port]`.
+///
+/// `GET` or `POST /vote?slot=1&emoji=grin` votes for an emoji in a slot, counting from 1.
+/// Voters are told apart by the `voter` parameter, their address when it is missing,
+/// and only their latest vote per slot counts.
+/// `GET /` lists the emoji ids and the current tally.
+#[derive(Resource, Default)]
+pub struct AudienceServer {
+    listener: Option<TcpListener>,
+    /// Requests still being read or answered.
+    requests: Vec<VoteRequest>,
+    /// Requests of every address in the current second.
+    rates: HashMap<IpAddr, RequestRate>,
+}
+
+/// Requests from a single address since `window_start`.
+struct RequestRate {
+    window_start: f32,
+    count: u32,
+}
+
+/// Request of a voter, read and answered over several frames so the game never waits on it.
+struct VoteRequest {
+    stream: TcpStream,
+    address: SocketAddr,
+    received: Vec<u8>,
+    /// Response bytes not written yet, empty until the whole request was received.
+    response: Vec<u8>,
+    accepted_at: f32,
+    /// Whether its address sent too many requests, answered without being counted.
+    limited: bool,
+}
+
+impl AudienceServer {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+
+        let Some(index) = args.iter().position(|arg| arg == "--audience") else {
+            return Self::default();
+        };
+
+        let port: u16 = args
+            .get(index + 1)
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT);
+
+        match TcpListener::bind(("0.0.0.0", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        {
+            Ok(listener) => {
+                info!("Audience votes open at http://localhost:{port}/vote");
+                Self {
+                    listener: Some(listener),
+                    ..default()
+                }
+            }
+            Err(err) => {
+                error!("Unable to take audience votes on port {port}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.listener.is_some()
+    }
+}
+
+/// Votes of the audience for the current round.
+#[derive(Resource, Default)]
+pub struct AudienceVotes {
+    /// Emoji index voted by every voter id, one map per slot.
+    pub slots: Vec<HashMap<String, usize>>,
+    /// The crowd's consensus scored against the answer, once revealed.
+    pub result: Option<RoundResult>,
+}
+
+impl AudienceVotes {
+    /// Emoji indices and their vote count from the most voted, ties going to the lowest index.
+    pub fn tally(&self, slot: usize) -> Vec<(usize, usize)> {
+        let mut counts: HashMap<usize, usize> = HashMap::default();
+        for &emoji in self.slots[slot].values() {
+            *counts.entry(emoji).or_default() += 1;
+        }
+
+        let mut tally: Vec<(usize, usize)> = counts.into_iter().collect();
+        tally.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        tally
+    }
+
+    /// Most voted emoji of every slot, -1 for slots without votes.
+    pub fn consensus(&self) -> Vec<i32> {
+        (0..self.slots.len())
+            .map(|slot| {
+                self.tally(slot)
+                    .first()
+                    .map(|&(emoji, _)| emoji as i32)
+                    .unwrap_or(-1)
+            })
+            .collect()
+    }
+}
+
+/// Marker for the vote histogram below a placement tile.
+#[derive(Component)]
+pub struct VoteHistogram;
+
+/// Marker for the crowd's result shown during the reveal.
+#[derive(Component)]
+pub struct CrowdLabel;
+
+/// Clears the votes at the start of every round.
+pub fn reset_votes(
+    mut votes: ResMut<AudienceVotes>,
+    audience: Res<AudienceServer>,
+    settings: Res<GameSettings>,
+) {
+    if audience.is_enabled() == false {
+        return;
+    }
+
+    *votes = AudienceVotes {
+        slots: vec![HashMap::default(); settings.answer_len()],
+        result: None,
+    };
+}
+
+/// Reads and answers the requests of voters, votes only count while the round is being guessed.
+pub fn accept_votes(
+    mut audience: ResMut<AudienceServer>,
+    mut votes: ResMut<AudienceVotes>,
+    emoji_map: Res<EmojiMap>,
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+) {
+    let AudienceServer {
+        listener,
+        requests,
+        rates,
+    } = audience.as_mut();
+    let Some(listener) = listener.as_ref() else {
+        return;
+    };
+
+    loop {
+        let (stream, address) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => {
+                warn!("Unable to accept audience request: {err}");
+                break;
+            }
+        };
+
+        if let Err(err) = stream.set_nonblocking(true) {
+            debug!("Audience request from {address} failed: {err}");
+            continue;
+        }
+
+        requests.push(VoteRequest {
+            stream,
+            address,
+            received: Vec::new(),
+            response: Vec::new(),
+            accepted_at: time.elapsed_seconds(),
+            limited: allow_request(rates, address.ip(), time.elapsed_seconds()) == false,
+        });
+    }
+    rates.retain(|_, rate| time.elapsed_seconds() - rate.window_start < 1.0);
+
+    requests.retain_mut(|request| {
+        if time.elapsed_seconds() - request.accepted_at >= REQUEST_TIMEOUT {
+            debug!("Audience request from {} timed out", request.address);
+            return false;
+        }
+
+        match progress_request(request, &mut votes, &emoji_map, &game_state) {
+            Ok(done) => done == false,
+            Err(err) => {
+                debug!("Audience request from {} failed: {err}", request.address);
+                false
+            }
+        }
+    });
+}
+
+/// Reads what arrived of a request, answering it once complete, true when the response was sent.
+fn progress_request(
+    request: &mut VoteRequest,
+    votes: &mut ResMut<AudienceVotes>,
+    emoji_map: &EmojiMap,
+    game_state: &State<GameState>,
+) -> io::Result<bool> {
+    if request.response.is_empty() {
+        // Voters may stop sending once the request is out
+        let mut closed: bool = false;
+        let mut buffer: [u8; 1024] = [0; 1024];
+        loop {
+            match request.stream.read(&mut buffer) {
+                Ok(0) => {
+                    closed = true;
+                    break;
+                }
+                Ok(len) => request.received.extend_from_slice(&buffer[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+
+            if request.received.len() > MAX_REQUEST_LEN {
+                break;
+            }
+        }
+
+        let (status, text): (&str, String) = match request_len(&request.received) {
+            _ if request.received.len() > MAX_REQUEST_LEN => {
+                ("413 Payload Too Large", "Request is too long".into())
+            }
+            Some(_) if request.limited => (
+                "429 Too Many Requests",
+                "Too many requests from this address".into(),
+            ),
+            Some(len) if request.received.len() >= len => {
+                let text: String = String::from_utf8_lossy(&request.received[..len]).into_owned();
+                answer_request(&text, request.address, votes, emoji_map, game_state)
+            }
+            _ if closed => return Err(io::ErrorKind::UnexpectedEof.into()),
+            // Waiting for the rest of the request
+            _ => return Ok(false),
+        };
+
+        request.response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Access-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{text}",
+            text.len()
+        )
+        .into_bytes();
+    }
+
+    while request.response.is_empty() == false {
+        match request.stream.write(&request.response) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(len) => {
+                request.response.drain(..len);
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(true)
+}
+
+/// Length of the request once its headers are complete, counting the body announced by `Content-Length`.
+fn request_len(received: &[u8]) -> Option<usize> {
+    let headers_end: usize = received
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")?
+        + 4;
+
+    let headers: String = String::from_utf8_lossy(&received[..headers_end]).into_owned();
+    let body_len: usize = headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, len)| len.trim().parse().ok())
+        .unwrap_or(0);
+
+    Some(headers_end.saturating_add(body_len))
+}
+
+/// Status and text of the response to a whole request.
+fn answer_request(
+    request: &str,
+    address: SocketAddr,
+    votes: &mut ResMut<AudienceVotes>,
+    emoji_map: &EmojiMap,
+    game_state: &State<GameState>,
+) -> (&'static str, String) {
+    // Parameters come from the query string, or the body of a form post
+    let target: &str = request.split_whitespace().nth(1).unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let body: &str = request
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or("");
+    let params: HashMap<String, String> = parse_params(query)
+        .into_iter()
+        .chain(parse_params(body))
+        .collect();
+
+    match path {
+        "/" => ("200 OK", describe(votes, emoji_map)),
+        "/vote" if *game_state.get() != GameState::InGame => {
+            ("409 Conflict", "Voting is closed".into())
+        }
+        "/vote" => match record_vote(votes, emoji_map, &params, address.ip()) {
+            Ok(text) => ("200 OK", text),
+            Err(text) => ("400 Bad Request", text),
+        },
+        _ => ("404 Not Found", "Unknown path, try /vote".into()),
+    }
+}
+
+/// Whether another request from `address` is answered, counting it.
+fn allow_request(rates: &mut HashMap<IpAddr, RequestRate>, address: IpAddr, now: f32) -> bool {
+    let rate: &mut RequestRate = rates.entry(address).or_insert(RequestRate {
+        window_start: now,
+        count: 0,
+    });
+    if now - rate.window_start >= 1.0 {
+        rate.window_start = now;
+        rate.count = 0;
+    }
+
+    rate.count += 1;
+    rate.count <= MAX_REQUESTS_PER_SECOND
+}
+
+/// Records the vote of the `voter` parameter, falling back to the address of the request.
+fn record_vote(
+    votes: &mut AudienceVotes,
+    emoji_map: &EmojiMap,
+    params: &HashMap<String, String>,
+    address: IpAddr,
+) -> Result<String, String> {
+    let voter: String = params
+        .get("voter")
+        .filter(|voter| voter.is_empty() == false)
+        .cloned()
+        .unwrap_or_else(|| address.to_string());
+    if voter.len() > MAX_VOTER_LEN {
+        return Err(format!("voter has to be at most {MAX_VOTER_LEN} bytes"));
+    }
+
+    let slot: usize = params
+        .get("slot")
+        .and_then(|slot| slot.parse::<usize>().ok())
+        .filter(|&slot| slot >= 1 && slot <= votes.slots.len())
+        .ok_or_else(|| format!("slot has to be between 1 and {}", votes.slots.len()))?;
+
+    let emoji_id: &str = params
+        .get("emoji")
+        .map(String::as_str)
+        .ok_or("emoji is missing")?;
+    // Ids and display names both work
+    let emoji: usize = emoji_map
+        .indices
+        .get(emoji_id)
+        .copied()
+        .or_else(|| {
+            emoji_map
+                .data
+                .iter()
+                .position(|data| data.name.eq_ignore_ascii_case(emoji_id))
+        })
+        .ok_or_else(|| format!("unknown emoji \"{emoji_id}\""))?;
+
+    if votes.slots[slot - 1].len() >= MAX_VOTERS
+        && votes.slots[slot - 1].contains_key(&voter) == false
+    {
+        return Err("slot has too many voters".into());
+    }
+
+    votes.slots[slot - 1].insert(voter, emoji);
+    Ok(format!("Voted {} for slot {slot}", emoji_map.names[emoji]))
+}
+
+/// Emoji ids followed by the current tally of every slot.
+fn describe(votes: &AudienceVotes, emoji_map: &EmojiMap) -> String {
+    let mut text: String = format!("Emojis: {}\n", emoji_map.names.join(", "));
+
+    for slot in 0..votes.slots.len() {
+        let tally: Vec<String> = votes
+            .tally(slot)
+            .iter()
+            .map(|&(emoji, count)| format!("{} {count}", emoji_map.names[emoji]))
+            .collect();
+        text.push_str(&format!("Slot {}: {}\n", slot + 1, tally.join(", ")));
+    }
+
+    text
+}
+
+/// Splits `key=value&key=value` pairs, decoding `+` and `%XX` escapes.
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    params
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect()
+}
+
+fn decode(text: &str) -> String {
+    let bytes: &[u8] = text.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).trim().to_string()
+}
+
+/// Scores the crowd's consensus against the answer of the round.
+pub fn score_crowd(
+    mut ev_round_scored: EventReader<RoundScored>,
+    mut votes: ResMut<AudienceVotes>,
+    audience: Res<AudienceServer>,
+    random_number: Res<RandomNumber>,
+    settings: Res<GameSettings>,
+) {
+    for _ in ev_round_scored.read() {
+        if audience.is_enabled() {
+            let consensus: Vec<i32> = votes.consensus();
+            votes.result = Some(RoundResult::new(
+                &consensus,
+                &random_number.numbers,
+                settings.ordered,
+            ));
+        }
+    }
+}
+
+/// Shows the most voted emojis below every placement tile, and the crowd's result once revealed.
+pub fn spawn_histograms(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_labels: Query<Entity, Or<(With<VoteHistogram>, With<CrowdLabel>)>>,
+    q_placement_tiles: Query<(Entity, &EmojiTile), With<PlacementMenuBtn>>,
+    votes: Res<AudienceVotes>,
+    guesses: Res<EmojiGuesses>,
+    emoji_map: Res<EmojiMap>,
+    game_state: Res<State<GameState>>,
+) {
+    for entity in q_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if votes.slots.is_empty()
+        || matches!(
+            game_state.get(),
+            GameState::InGame | GameState::RoundReveal | GameState::Paused
+        ) == false
+    {
+        return;
+    }
+
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+    let [r, g, b, _] = palette.get_or_default(&ColorKey::Purple).as_rgba_u8();
+    let tile_size: f32 = guesses.tile_scale * 200.0;
+
+    let header: String = format!(
+        r###"
+        #set page(width: {tile_size}pt, height: auto, margin: 4pt)
+        #set text(size: 11pt, font: "consolas", fill: rgb("#FCFCFA"))
+        "###
+    );
+
+    for (tile, emoji_tile) in q_placement_tiles.iter() {
+        let Some(tally) = votes
+            .slots
+            .get(emoji_tile.index)
+            .map(|_| votes.tally(emoji_tile.index))
+        else {
+            continue;
+        };
+
+        let most_votes: usize = tally.first().map(|&(_, count)| count).unwrap_or(1);
+        let rows: Vec<String> = tally
+            .iter()
+            .take(HISTOGRAM_ROWS)
+            .map(|&(emoji, count)| {
+                let width: f32 = count as f32 / most_votes as f32 * tile_size * 0.4;
+                format!(
+                    "#box(width: {width}pt, height: 8pt, fill: rgb({r}, {g}, {b})) {} {count}",
+                    emoji_map.data[emoji].name
+                )
+            })
+            .collect();
+
+        if rows.is_empty() {
+            continue;
+        }
+
+        let Ok(label_tree) = typst_compiler.compile_flatten(
+            &mut commands,
+            &mut fragments,
+            header.clone() + &rows.join(" \\\n"),
+        ) else {
+            continue;
+        };
+
+        // Just below the tile
+        commands
+            .entity(label_tree.root_entity)
+            .insert((
+                Transform::from_xyz(-tile_size * 0.5, -tile_size * 0.5 - 4.0, 2.0),
+                VoteHistogram,
+            ))
+            .set_parent(tile);
+    }
+
+    let Some(result) = votes.result.as_ref() else {
+        return;
+    };
+
+    let squares: String = result
+        .slots
+        .iter()
+        .map(|slot| match slot {
+            SlotResult::Exact => '🟩',
+            SlotResult::Partial => '🟨',
+            SlotResult::Miss => '⬛',
+        })
+        .collect();
+
+    let Ok(label_tree) = typst_compiler.compile_flatten(
+        &mut commands,
+        &mut fragments,
+        r###"
+        #set page(width: 220pt, margin: 4pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+        "###
+        .to_string()
+            + &format!("*Crowd* \\ {squares} +{}", result.points),
+    ) else {
+        return;
+    };
+
+    commands
+        .entity(label_tree.root_entity)
+        .insert((Transform::from_xyz(410.0, 280.0, 1.0), CrowdLabel));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voter(id: u8) -> String {
+        format!("viewer{id}")
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn decode_handles_plus_and_percent_escapes() {
+        assert_eq!(decode("thumbs+up"), "thumbs up");
+        assert_eq!(decode("%F0%9F%98%80"), "😀");
+        assert_eq!(decode("100%25"), "100%");
+        // Malformed escapes are kept as they are
+        assert_eq!(decode("50%"), "50%");
+        assert_eq!(decode("%zzgrin"), "%zzgrin");
+        assert_eq!(decode("%é"), "%é");
+    }
+
+    #[test]
+    fn parse_params_skips_pairs_without_a_value() {
+        assert_eq!(
+            parse_params("slot=2&emoji=grin+face&voter&=x"),
+            [
+                ("slot".to_string(), "2".to_string()),
+                ("emoji".to_string(), "grin face".to_string()),
+                (String::new(), "x".to_string()),
+            ]
+        );
+        assert!(parse_params("").is_empty());
+    }
+
+    #[test]
+    fn tally_breaks_ties_by_the_lowest_emoji() {
+        let mut votes: AudienceVotes = AudienceVotes {
+            slots: vec![HashMap::default()],
+            result: None,
+        };
+        votes.slots[0].insert(voter(1), 7);
+        votes.slots[0].insert(voter(2), 3);
+        votes.slots[0].insert(voter(3), 7);
+        votes.slots[0].insert(voter(4), 3);
+        votes.slots[0].insert(voter(5), 1);
+
+        assert_eq!(votes.tally(0), [(3, 2), (7, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn consensus_leaves_slots_without_votes_empty() {
+        let mut votes: AudienceVotes = AudienceVotes {
+            slots: vec![HashMap::default(); 3],
+            result: None,
+        };
+        votes.slots[1].insert(voter(1), 4);
+        // A voter's latest vote replaces the previous one
+        votes.slots[1].insert(voter(2), 9);
+        votes.slots[1].insert(voter(2), 4);
+
+        assert_eq!(votes.consensus(), [-1, 4, -1]);
+        assert_eq!(votes.tally(1), [(4, 2)]);
+    }
+
+    #[test]
+    fn votes_are_told_apart_by_voter_id() {
+        let mut votes: AudienceVotes = AudienceVotes {
+            slots: vec![HashMap::default()],
+            result: None,
+        };
+        let mut emoji_map: EmojiMap = EmojiMap::default();
+        emoji_map.names = vec!["grin".into(), "cry".into()];
+        emoji_map.indices = HashMap::from_iter([("grin".into(), 0), ("cry".into(), 1)]);
+        let address: IpAddr = IpAddr::from([192, 168, 0, 1]);
+
+        // Viewers behind the same address
+        let vote =
+            |voter: &str, emoji: &str| params(&[("slot", "1"), ("emoji", emoji), ("voter", voter)]);
+        assert!(record_vote(&mut votes, &emoji_map, &vote("ann", "grin"), address).is_ok());
+        assert!(record_vote(&mut votes, &emoji_map, &vote("bo", "cry"), address).is_ok());
+        assert!(record_vote(&mut votes, &emoji_map, &vote("cy", "cry"), address).is_ok());
+        // Without an id the address votes
+        let anonymous = params(&[("slot", "1"), ("emoji", "grin")]);
+        assert!(record_vote(&mut votes, &emoji_map, &anonymous, address).is_ok());
+
+        assert_eq!(votes.tally(0), [(0, 2), (1, 2)]);
+        assert!(votes.slots[0].contains_key("192.168.0.1"));
+
+        let long_id: String = "x".repeat(MAX_VOTER_LEN + 1);
+        assert!(record_vote(&mut votes, &emoji_map, &vote(&long_id, "grin"), address).is_err());
+    }
+
+    #[test]
+    fn requests_are_limited_per_address_each_second() {
+        let mut rates: HashMap<IpAddr, RequestRate> = HashMap::default();
+        let address: IpAddr = IpAddr::from([192, 168, 0, 1]);
+
+        for _ in 0..MAX_REQUESTS_PER_SECOND {
+            assert!(allow_request(&mut rates, address, 0.5));
+        }
+        assert!(allow_request(&mut rates, address, 0.9) == false);
+        // Other addresses have their own limit
+        assert!(allow_request(
+            &mut rates,
+            IpAddr::from([192, 168, 0, 2]),
+            0.9
+        ));
+        // A new second starts over
+        assert!(allow_request(&mut rates, address, 1.5));
+    }
+
+    #[test]
+    fn request_len_waits_for_the_announced_body() {
+        assert_eq!(request_len(b"GET /vote?slot=1 HTTP/1.1\r\nHost: x"), None);
+        assert_eq!(request_len(b"GET / HTTP/1.1\r\n\r\n"), Some(18));
+
+        let post: &[u8] = b"POST /vote HTTP/1.1\r\ncontent-length: 17\r\n\r\nslot=1&emoji=";
+        assert_eq!(request_len(post), Some(post.len() + 4));
+    }
+}
@@ -0,0 +1,27 @@
+//! Headless host for LAN games, running the round logic without rendering or audio.
+//!
+//! `cargo run --bin moji-server -- [--host <port>] [--seed <number>]`
+
+use std::time::Duration;
+
+use bevy::{app::ScheduleRunnerPlugin, log::LogPlugin, prelude::*};
+
+use ggj2024::server;
+
+/// Rounds are checked this many times per second.
+const TICK_RATE: f64 = 30.0;
+
+fn main() {
+    let mut app: App = App::new();
+    app
+        // Bevy plugins
+        .add_plugins(
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1.0 / TICK_RATE,
+            ))),
+        )
+        .add_plugins(LogPlugin::default());
+
+    server::build(&mut app, server::host_from_args());
+    app.run();
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+use chrono::{Datelike, Local, NaiveDate};
+use motiongfx_typst::TypstCompiler;
+
+use crate::game::GameState;
+use crate::lan::Lan;
+use crate::save::SaveData;
+use crate::score::{Score, SlotResult};
+use crate::settings::GameSettings;
+use crate::{menu_ui, mouse};
+
+/// Today's challenge, shared by every player through a seed derived from the local date.
+#[derive(Resource, Default)]
+pub struct DailyChallenge {
+    /// Whether the current game is the daily challenge.
+    pub active: bool,
+    pub date: NaiveDate,
+    /// Settings to restore once the challenge is over.
+    previous_settings: Option<GameSettings>,
+    /// Whether the result of this attempt was submitted, playing again does not count.
+    submitted: bool,
+}
+
+impl DailyChallenge {
+    /// Date as a readable `YYYYMMDD` number.
+    pub fn seed(&self) -> u64 {
+        self.date.year() as u64 * 10000 + self.date.month() as u64 * 100 + self.date.day() as u64
+    }
+
+    pub fn date_key(&self) -> String {
+        self.date.format("%Y-%m-%d").to_string()
+    }
+
+    /// The player's own settings while the challenge is being played.
+    pub fn player_settings(&self) -> Option<&GameSettings> {
+        self.previous_settings.as_ref()
+    }
+}
+
+/// Marker for the daily challenge message on the main menu.
+#[derive(Component)]
+pub struct DailyLabel;
+
+/// Builds a spoiler free summary of a game, one row of squares per round.
+pub fn share_text(date_key: &str, score: &Score) -> String {
+    let mut text: String = format!("Moji Moji Daily {date_key}\nScore: {}", score.total);
+
+    for round in score.rounds.iter() {
+        text.push('\n');
+        for slot in round.slots.iter() {
+            text.push(match slot {
+                SlotResult::Exact => '🟩',
+                SlotResult::Partial => '🟨',
+                SlotResult::Miss => '⬛',
+            });
+        }
+    }
+
+    text
+}
+
+/// Starts today's challenge with the default settings, unless it was already played.
+pub fn daily_button_evt(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_daily_btns: Query<&menu_ui::DailyBtn>,
+    q_daily_labels: Query<Entity, With<DailyLabel>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut daily: ResMut<DailyChallenge>,
+    mut settings: ResMut<GameSettings>,
+    mut save_data: ResMut<SaveData>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    lan: Res<Lan>,
+) {
+    for clicked in ev_clicked.read() {
+        // LAN clients play the rounds of the host
+        if q_daily_btns.contains(clicked.entity) == false || lan.is_client() {
+            continue;
+        }
+
+        daily.date = Local::now().date_naive();
+
+        if save_data.daily_date == daily.date_key() {
+            for entity in q_daily_labels.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+
+            spawn_daily_label(
+                &mut commands,
+                &mut fragments,
+                &mut typst_compiler,
+                &format!(
+                    "Already played today, come back tomorrow! \\\n{}",
+                    save_data.daily_summary.replace('\n', " \\\n")
+                ),
+            );
+            continue;
+        }
+
+        // Recorded right away so leaving halfway does not allow another attempt
+        save_data.daily_date = daily.date_key();
+        save_data.daily_summary = format!("Moji Moji Daily {}\nDid not finish", daily.date_key());
+        save_data.write();
+
+        daily.active = true;
+        daily.submitted = false;
+        daily.previous_settings = Some(settings.clone());
+        *settings = GameSettings::default();
+        next_game_state.set(GameState::InGame);
+    }
+}
+
+/// Submits the result of today's challenge, only the first attempt of a day counts.
+pub fn submit(
+    mut daily: ResMut<DailyChallenge>,
+    score: Res<Score>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if daily.active == false || daily.submitted {
+        return;
+    }
+    daily.submitted = true;
+
+    let summary: String = share_text(&daily.date_key(), &score);
+    info!("Daily challenge result:\n{summary}");
+
+    save_data.daily_date = daily.date_key();
+    save_data.daily_summary = summary;
+    save_data.write();
+}
+
+/// Leaves the daily challenge and restores the player's own settings.
+pub fn end_challenge(mut daily: ResMut<DailyChallenge>, mut settings: ResMut<GameSettings>) {
+    daily.active = false;
+    if let Some(previous_settings) = daily.previous_settings.take() {
+        *settings = previous_settings;
+    }
+}
+
+pub fn despawn_daily_label(
+    mut commands: Commands,
+    q_daily_labels: Query<Entity, With<DailyLabel>>,
+) {
+    for entity in q_daily_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_daily_label(
+    commands: &mut Commands,
+    fragments: &mut ResMut<Assets<VelloFragment>>,
+    typst_compiler: &mut ResMut<TypstCompiler>,
+    text: &str,
+) {
+    let header: String = r###"
+        #set page(width: 400pt, margin: 8pt)
+        #set text(size: 14pt, font: "consolas", fill: rgb("#FCFCFA"))
+        #set align(center)
+    "###
+    .into();
+
+    let Ok(label_tree) = typst_compiler.compile_flatten(commands, fragments, header + text) else {
+        return;
+    };
+
+    commands.entity(label_tree.root_entity).insert((
+        Transform::from_xyz(-label_tree.size.x * 0.5, 340.0, 1.0),
+        DailyLabel,
+    ));
+}
@@ -1,130 +1,639 @@
 use bevy::{
+    asset::{
+        io::Reader, AssetLoader, AssetPath, AsyncReadExt, LoadContext, LoadState, LoadedFolder,
+        RecursiveDependencyLoadState, UntypedAssetId,
+    },
+    audio::Source,
     prelude::*,
-    utils::{synccell::SyncCell, HashMap},
+    utils::{BoxedFuture, HashMap, HashSet},
 };
-use rand::{rngs::ThreadRng, Rng};
-use std::fs;
+use bevy_prng::ChaCha8Rng;
+use bevy_rand::prelude::GlobalEntropy;
+use rand::Rng;
+use serde::Deserialize;
+use std::fmt;
+use std::path::PathBuf;
 
-use crate::emoji_ui;
+use crate::lan::Lan;
+use crate::settings::{ClipPlayback, GameSettings};
+use crate::{emoji_ui, game::GameState};
 
 #[derive(Event)]
-pub struct PlaySound;
+pub struct PlaySound {
+    /// Plays only the clip of this slot when set, otherwise the whole answer.
+    pub slot: Option<usize>,
+}
 
 #[derive(Event)]
 pub struct GenerateRandomNumber;
 
+/// Path to the emoji pack, relative to the assets folder.
+const PACK_PATH: &str = "emojis.pack.ron";
+/// Folders that emoji assets are expected to live in, used to detect orphaned files.
+const VECTOR_FOLDER: &str = "emoji";
+const AUDIO_FOLDER: &str = "emoji-sound-ogg";
+
+/// A single entry in the emoji pack file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmojiEntry {
+    pub id: String,
+    pub name: String,
+    pub vector: String,
+    pub audio: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// On disk layout of an emoji pack.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmojiManifest {
+    pub emojis: Vec<EmojiEntry>,
+}
+
+/// A loaded emoji with handles to its assets.
+#[derive(Debug, Clone)]
+pub struct EmojiPackEntry {
+    pub id: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub vector: Handle<bevy_vello::VelloVector>,
+    pub audio: Handle<AudioSource>,
+}
+
+/// A list of emojis loaded through the [`AssetServer`].
+#[derive(Asset, TypePath, Debug)]
+pub struct EmojiPack {
+    pub emojis: Vec<EmojiPackEntry>,
+}
+
+#[derive(Debug)]
+pub enum EmojiPackError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    DuplicateId(String),
+}
+
+impl fmt::Display for EmojiPackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Unable to read emoji pack: {err}"),
+            Self::Parse(err) => write!(f, "Unable to parse emoji pack: {err}"),
+            Self::DuplicateId(id) => write!(f, "Emoji \"{id}\" is listed more than once"),
+        }
+    }
+}
+
+impl std::error::Error for EmojiPackError {}
+
+impl From<std::io::Error> for EmojiPackError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for EmojiPackError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+#[derive(Default)]
+pub struct EmojiPackLoader;
+
+impl AssetLoader for EmojiPackLoader {
+    type Asset = EmojiPack;
+    type Settings = ();
+    type Error = EmojiPackError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes: Vec<u8> = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let manifest: EmojiManifest = ron::de::from_bytes(&bytes)?;
+
+            let mut seen_ids: HashSet<&str> = HashSet::default();
+            let mut emojis: Vec<EmojiPackEntry> = Vec::with_capacity(manifest.emojis.len());
+
+            for entry in manifest.emojis.iter() {
+                if seen_ids.insert(&entry.id) == false {
+                    return Err(EmojiPackError::DuplicateId(entry.id.clone()));
+                }
+
+                emojis.push(EmojiPackEntry {
+                    id: entry.id.clone(),
+                    name: entry.name.clone(),
+                    tags: entry.tags.clone(),
+                    vector: load_context.load(entry.vector.clone()),
+                    audio: load_context.load(entry.audio.clone()),
+                });
+            }
+
+            Ok(EmojiPack { emojis })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pack.ron"]
+    }
+}
+
 #[derive(Default)]
 pub struct EmojiData {
+    pub name: String,
+    pub tags: Vec<String>,
     pub audio_handle: Handle<AudioSource>,
     pub vector_handle: Handle<bevy_vello::VelloVector>,
 }
 
+/// Every loaded emoji, indexed by position with a lookup from emoji id.
 #[derive(Resource, Default)]
 pub struct EmojiMap {
+    /// Emoji ids.
     pub names: Vec<String>,
     pub data: Vec<EmojiData>,
+    pub indices: HashMap<String, usize>,
+    pub pack: Handle<EmojiPack>,
+    /// Emoji asset folders, only used to report orphaned files.
+    pub folders: Vec<Handle<LoadedFolder>>,
+}
+
+impl EmojiMap {
+    pub fn get(&self, id: &str) -> Option<&EmojiData> {
+        self.indices.get(id).map(|&index| &self.data[index])
+    }
+
+    /// Load state of the emoji pack and every asset it references.
+    pub fn load_state(&self, asset_server: &AssetServer) -> RecursiveDependencyLoadState {
+        asset_server.recursive_dependency_load_state(&self.pack)
+    }
+
+    /// Drops every emoji that has an asset which failed to load.
+    fn retain_loaded(&mut self, asset_server: &AssetServer) {
+        let mut index: usize = 0;
+        while index < self.data.len() {
+            let data: &EmojiData = &self.data[index];
+            if asset_server.load_state(&data.vector_handle) == LoadState::Failed
+                || asset_server.load_state(&data.audio_handle) == LoadState::Failed
+            {
+                warn!(
+                    "Skipping emoji \"{}\" as its assets failed to load",
+                    self.names[index]
+                );
+                self.names.remove(index);
+                self.data.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+
+        self.indices.clear();
+        for (index, name) in self.names.iter().enumerate() {
+            self.indices.insert(name.clone(), index);
+        }
+    }
+
+    fn rebuild(&mut self, pack: &EmojiPack) {
+        self.names.clear();
+        self.data.clear();
+        self.indices.clear();
+
+        for entry in pack.emojis.iter() {
+            self.indices.insert(entry.id.clone(), self.names.len());
+            self.names.push(entry.id.clone());
+            self.data.push(EmojiData {
+                name: entry.name.clone(),
+                tags: entry.tags.clone(),
+                audio_handle: entry.audio.clone(),
+                vector_handle: entry.vector.clone(),
+            });
+        }
+    }
 }
 
 #[derive(Component)]
-pub struct EmojiAudio;
+pub struct EmojiAudio {
+    /// Placement slot the clip belongs to.
+    pub slot: usize,
+}
 
-pub fn load_emoji_data(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
+/// Clip of a single emoji played from the emoji menu.
+#[derive(Component)]
+pub struct EmojiPreview;
+
+/// A clip of the answer waiting for its turn to be played.
+pub struct ScheduledClip {
+    /// Placement slot the clip belongs to.
+    pub slot: usize,
+    /// Seconds after the start of playback.
+    pub start: f32,
+    pub duration: f32,
+    pub handle: Handle<AudioSource>,
+}
+
+/// Clips of the current playback, in answer order.
+#[derive(Resource, Default)]
+pub struct AudioSchedule {
+    pub clips: Vec<ScheduledClip>,
+    /// Seconds since playback started, not counting time spent paused.
+    pub elapsed: f32,
+    /// Number of clips that have already been spawned.
+    started: usize,
+}
+
+/// Length of each clip in seconds, measured once by decoding it.
+#[derive(Resource, Default)]
+pub struct ClipDurations(HashMap<AssetId<AudioSource>, f32>);
+
+impl ClipDurations {
+    pub fn contains(&self, handle: &Handle<AudioSource>) -> bool {
+        self.0.contains_key(&handle.id())
+    }
+
+    /// Decodes the clip to measure it the first time, which is slow for ogg streams.
+    /// Clips are measured while loading, see [`track_loading`].
+    pub fn get_or_measure(
+        &mut self,
+        handle: &Handle<AudioSource>,
+        sources: &Assets<AudioSource>,
+    ) -> f32 {
+        if let Some(&duration) = self.0.get(&handle.id()) {
+            return duration;
+        }
+
+        let Some(source) = sources.get(handle) else {
+            return 0.0;
+        };
+
+        let decoder = source.decoder();
+        let duration: f32 = match decoder.total_duration() {
+            Some(duration) => duration.as_secs_f32(),
+            // Ogg streams do not know their length up front
+            None => {
+                let samples_per_sec: f32 = decoder.channels() as f32 * decoder.sample_rate() as f32;
+                decoder.count() as f32 / samples_per_sec
+            }
+        };
+
+        self.0.insert(handle.id(), duration);
+        duration
+    }
+}
+
+/// Reads the emoji pack straight from disk, for when there is no [`AssetServer`].
+pub fn read_manifest() -> Result<EmojiManifest, EmojiPackError> {
+    let bytes: Vec<u8> = std::fs::read(asset_root().join(PACK_PATH))?;
+    Ok(ron::de::from_bytes(&bytes)?)
+}
+
+/// Folder the [`AssetServer`] loads from, found the same way Bevy does:
+/// `BEVY_ASSET_ROOT`, then `CARGO_MANIFEST_DIR`, then next to the executable.
+fn asset_root() -> PathBuf {
+    let base: PathBuf = std::env::var_os("BEVY_ASSET_ROOT")
+        .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR"))
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(PathBuf::from))
+        })
+        .unwrap_or_default();
+
+    base.join("assets")
+}
+
+pub fn load_emoji_data(asset_server: Res<AssetServer>, mut emoji_map: ResMut<EmojiMap>) {
+    emoji_map.pack = asset_server.load(PACK_PATH);
+    emoji_map.folders = vec![
+        asset_server.load_folder(VECTOR_FOLDER),
+        asset_server.load_folder(AUDIO_FOLDER),
+    ];
+}
+
+/// Fills [`EmojiMap`] once the emoji pack is loaded.
+/// Packs are not hot-reloaded, the emoji menu is only built once loading is done.
+pub fn sync_emoji_map(
+    mut ev_asset: EventReader<AssetEvent<EmojiPack>>,
+    packs: Res<Assets<EmojiPack>>,
     mut emoji_map: ResMut<EmojiMap>,
 ) {
-    let audio_folder = "emoji-sound-ogg/";
-    let vector_folder = "emoji/";
+    for event in ev_asset.read() {
+        match event {
+            AssetEvent::Added { id } if *id == emoji_map.pack.id() => {
+                let Some(pack) = packs.get(*id) else {
+                    continue;
+                };
+
+                emoji_map.rebuild(pack);
+                info!(
+                    "Loaded {} emojis from \"{PACK_PATH}\"",
+                    emoji_map.names.len()
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reports emojis whose assets failed to load and asset files that no emoji refers to.
+pub fn report_emoji_errors(
+    asset_server: Res<AssetServer>,
+    packs: Res<Assets<EmojiPack>>,
+    folders: Res<Assets<LoadedFolder>>,
+    emoji_map: Res<EmojiMap>,
+    mut reported: Local<bool>,
+) {
+    if *reported {
+        return;
+    }
 
-    let Ok(audio_entries) = fs::read_dir("./assets/".to_string() + audio_folder) else {
+    let Some(pack) = packs.get(&emoji_map.pack) else {
+        if asset_server.load_state(&emoji_map.pack) == LoadState::Failed {
+            error!("Unable to load emoji pack \"{PACK_PATH}\"");
+            *reported = true;
+        }
         return;
     };
 
-    let Ok(mut vector_entires) = fs::read_dir("./assets/".to_string() + vector_folder) else {
+    let folders_done: bool = emoji_map.folders.iter().all(|folder| {
+        matches!(
+            asset_server.load_state(folder),
+            LoadState::Loaded | LoadState::Failed
+        )
+    });
+
+    if folders_done == false
+        || emoji_map.load_state(&asset_server) == RecursiveDependencyLoadState::Loading
+    {
         return;
-    };
+    }
+
+    *reported = true;
+
+    for entry in pack.emojis.iter() {
+        if asset_server.load_state(&entry.vector) == LoadState::Failed {
+            error!(
+                "Emoji \"{}\" is missing its vector file {:?}",
+                entry.id,
+                entry.vector.path()
+            );
+        }
+        if asset_server.load_state(&entry.audio) == LoadState::Failed {
+            error!(
+                "Emoji \"{}\" is missing its audio file {:?}",
+                entry.id,
+                entry.audio.path()
+            );
+        }
+    }
 
-    for audio_entry in audio_entries {
-        let audio_entry: fs::DirEntry = audio_entry.unwrap();
-        let vector_entry: fs::DirEntry = vector_entires.next().unwrap().unwrap();
+    let referenced: HashSet<AssetPath> = pack
+        .emojis
+        .iter()
+        .flat_map(|entry| [entry.vector.path(), entry.audio.path()])
+        .flatten()
+        .cloned()
+        .collect();
 
-        println!("{:#?}", audio_entry.file_name());
-        println!("{:#?}", vector_entry.file_name());
+    for folder in emoji_map.folders.iter().filter_map(|f| folders.get(f)) {
+        for path in folder.handles.iter().filter_map(|h| h.path()) {
+            if referenced.contains(path) == false {
+                warn!("\"{path}\" is not referenced by any emoji in \"{PACK_PATH}\"");
+            }
+        }
+    }
+}
 
-        let audio_path = audio_folder.to_owned() + audio_entry.file_name().to_str().unwrap();
-        let vector_path = vector_folder.to_owned() + vector_entry.file_name().to_str().unwrap();
+/// Number of emoji assets that have finished loading.
+#[derive(Resource, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    /// Why loading can not finish, shown instead of waiting forever.
+    pub error: Option<String>,
+}
 
-        let audio_handle: Handle<AudioSource> = asset_server.load(audio_path);
-        let vector_handle: Handle<bevy_vello::VelloVector> = asset_server.load(vector_path);
+impl LoadingProgress {
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.loaded as f32 / self.total as f32
+    }
+}
 
-        let name: String = audio_entry.file_name().into_string().unwrap();
+/// Tracks the vector and audio handles in [`EmojiMap`], measures the clips
+/// and leaves [`GameState::Loading`] once they are all done.
+pub fn track_loading(
+    asset_server: Res<AssetServer>,
+    mut emoji_map: ResMut<EmojiMap>,
+    mut progress: ResMut<LoadingProgress>,
+    mut clip_durations: ResMut<ClipDurations>,
+    audio_sources: Res<Assets<AudioSource>>,
+    packs: Res<Assets<EmojiPack>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if progress.error.is_some() {
+        return;
+    }
 
-        emoji_map
-            .names
-            .push(name.split('.').next().unwrap().to_owned());
+    if emoji_map.names.is_empty() {
+        let error: String = if asset_server.load_state(emoji_map.pack.id()) == LoadState::Failed {
+            format!("Unable to load \"assets/{PACK_PATH}\"")
+        } else if packs
+            .get(&emoji_map.pack)
+            .is_some_and(|pack| pack.emojis.is_empty())
+        {
+            format!("\"assets/{PACK_PATH}\" has no emojis")
+        } else {
+            return;
+        };
 
-        emoji_map.data.push(EmojiData {
-            audio_handle,
-            vector_handle,
-        })
+        error!("{error}");
+        progress.error = Some(error);
+        return;
+    }
+
+    let is_done = |id: UntypedAssetId| -> bool {
+        matches!(
+            asset_server.load_state(id),
+            LoadState::Loaded | LoadState::Failed
+        )
+    };
+
+    // One clip per frame so the bar keeps moving
+    if let Some(data) = emoji_map.data.iter().find(|data| {
+        asset_server.load_state(&data.audio_handle) == LoadState::Loaded
+            && clip_durations.contains(&data.audio_handle) == false
+    }) {
+        clip_durations.get_or_measure(&data.audio_handle, &audio_sources);
     }
 
-    for _ in 0..4 {
-        commands
-            .spawn(AudioBundle { ..default() })
-            .insert(EmojiAudio);
+    // Clips that failed to load are never measured
+    let is_measured = |handle: &Handle<AudioSource>| -> bool {
+        clip_durations.contains(handle) || asset_server.load_state(handle) == LoadState::Failed
+    };
+
+    progress.total = emoji_map.data.len() * 3;
+    progress.loaded = emoji_map
+        .data
+        .iter()
+        .map(|data| {
+            is_done(data.vector_handle.id().untyped()) as usize
+                + is_done(data.audio_handle.id().untyped()) as usize
+                + is_measured(&data.audio_handle) as usize
+        })
+        .sum();
+
+    if progress.loaded == progress.total {
+        emoji_map.retain_loaded(&asset_server);
+
+        if emoji_map.names.is_empty() {
+            let error: String = format!("Every emoji in \"assets/{PACK_PATH}\" failed to load");
+            error!("{error}");
+            progress.error = Some(error);
+            return;
+        }
+
+        next_game_state.set(GameState::Menu);
     }
 }
 
 #[derive(Resource, Default)]
 pub struct RandomNumber {
-    pub numbers: [usize; 4],
+    /// Emoji indices of the answer, one per placement tile.
+    pub numbers: Vec<usize>,
 }
 
 pub fn generate_random_num(
     mut random_number: ResMut<RandomNumber>,
     mut ev_generate_random_number: EventReader<GenerateRandomNumber>,
+    mut rng: ResMut<GlobalEntropy<ChaCha8Rng>>,
+    emoji_map: Res<EmojiMap>,
+    lan: Res<Lan>,
 ) {
-    let mut rng = rand::thread_rng();
+    let emoji_count: usize = emoji_map.names.len();
+
     for _ in ev_generate_random_number.read() {
-        for i in 0..4 {
+        // The host decides the answer of every round
+        if let Some(numbers) = lan.round_numbers() {
+            // Waits for the row to be rebuilt when the host changed the answer length
+            if numbers.len() == random_number.numbers.len() {
+                random_number.numbers.copy_from_slice(numbers);
+            }
+            continue;
+        }
+
+        if emoji_count < random_number.numbers.len() {
+            error!("Not enough emojis to generate a round, only {emoji_count} loaded");
+            continue;
+        }
+
+        for i in 0..random_number.numbers.len() {
             let mut random_value: usize;
             // Make sure no same random value!
             loop {
-                random_value = rng.gen_range(0..25);
+                random_value = rng.gen_range(0..emoji_count);
 
-                if emoji_ui::array_contain_number(&random_number.numbers, random_value) == false {
+                if emoji_ui::array_contain_number(&random_number.numbers[..i], random_value)
+                    == false
+                {
                     break;
                 }
             }
-            println!("Random value: {}", random_value);
+            debug!("Random value: {}", random_value);
             random_number.numbers[i] = random_value;
         }
     }
 }
 
+/// Schedules the clips of the answer according to [`GameSettings::playback`].
 pub fn play_audio(
     mut commands: Commands,
     mut ev_play_sound: EventReader<PlaySound>,
     emoji_entity: Query<Entity, With<EmojiAudio>>,
     random_num: Res<RandomNumber>,
     emoji_map: Res<EmojiMap>,
+    settings: Res<GameSettings>,
+    audio_sources: Res<Assets<AudioSource>>,
+    mut clip_durations: ResMut<ClipDurations>,
+    mut audio_schedule: ResMut<AudioSchedule>,
 ) {
-    for _ in ev_play_sound.read() {
-        let mut index: usize = 0;
+    for play_sound in ev_play_sound.read() {
         for entity in emoji_entity.iter() {
             commands.entity(entity).despawn();
+        }
+
+        let mut clips: Vec<ScheduledClip> = Vec::with_capacity(random_num.numbers.len());
+        let mut start: f32 = 0.0;
 
-            commands.spawn((
-                AudioBundle {
-                    source: emoji_map.data[random_num.numbers[index]]
-                        .audio_handle
-                        .clone(),
-                    ..default()
-                },
-                EmojiAudio,
-            ));
-            index += 1;
+        for (slot, &number) in random_num.numbers.iter().enumerate() {
+            if play_sound.slot.is_some_and(|only| only != slot) {
+                continue;
+            }
+
+            let handle: Handle<AudioSource> = emoji_map.data[number].audio_handle.clone();
+            let duration: f32 = clip_durations.get_or_measure(&handle, &audio_sources);
+
+            clips.push(ScheduledClip {
+                slot,
+                start,
+                duration,
+                handle,
+            });
+
+            start += match settings.playback {
+                ClipPlayback::Sequential => duration + settings.playback_gap.max(0.0),
+                ClipPlayback::Overlapped => (duration - settings.playback_overlap).max(0.0),
+                ClipPlayback::Simultaneous => 0.0,
+            };
+        }
+
+        *audio_schedule = AudioSchedule {
+            clips,
+            elapsed: 0.0,
+            started: 0,
+        };
+    }
+}
+
+/// Spawns scheduled clips once their start time is reached.
+pub fn schedule_audio(
+    mut commands: Commands,
+    mut audio_schedule: ResMut<AudioSchedule>,
+    time: Res<Time>,
+) {
+    audio_schedule.elapsed += time.delta_seconds();
+
+    while let Some(clip) = audio_schedule.clips.get(audio_schedule.started) {
+        if clip.start > audio_schedule.elapsed {
+            break;
         }
+
+        commands.spawn((
+            AudioBundle {
+                source: clip.handle.clone(),
+                ..default()
+            },
+            EmojiAudio { slot: clip.slot },
+        ));
+        audio_schedule.started += 1;
+    }
+}
+
+/// Stops playback, used when the answer changes or the board is left.
+pub fn stop_audio(
+    mut commands: Commands,
+    emoji_entity: Query<Entity, With<EmojiAudio>>,
+    mut audio_schedule: ResMut<AudioSchedule>,
+) {
+    for entity in emoji_entity.iter() {
+        commands.entity(entity).despawn();
     }
+
+    *audio_schedule = AudioSchedule::default();
 }
@@ -9,29 +9,58 @@ use motiongfx_typst::TypstCompiler;
 
 use crate::{
     emoji::{self, EmojiMap, RandomNumber},
-    game, menu_ui, mouse, SetupTimeline,
+    game,
+    hint::HintBudget,
+    lan::Lan,
+    menu_ui, mouse, party,
+    players::Roster,
+    score,
+    settings::GameSettings,
+    SetupTimeline,
 };
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct EmojiGuesses {
-    pub placement_tiles: [Option<Entity>; 4],
-    pub answer_tiles: [Option<Entity>; 4],
-    pub numbers: [i32; 4],
+    pub placement_tiles: Vec<Option<Entity>>,
+    pub answer_tiles: Vec<Option<Entity>>,
+    pub numbers: Vec<i32>,
+    /// Scale of the placement tiles relative to their largest size.
+    pub tile_scale: f32,
+    /// Rows of the other players guessing at the same time, see [`crate::party`].
+    pub rivals: Vec<RivalGuesses>,
 }
 
-impl Default for EmojiGuesses {
-    fn default() -> Self {
-        Self {
-            placement_tiles: [None; 4],
-            answer_tiles: [None; 4],
-            numbers: [-1, -1, -1, -1],
-        }
-    }
+/// Guesses of a player picking with a keyboard or gamepad cursor.
+/// Only the number of picks is shown so nobody can copy them from the shared screen.
+#[derive(Default)]
+pub struct RivalGuesses {
+    pub player: usize,
+    pub numbers: Vec<i32>,
+    /// Filled in once the round is scored.
+    pub result: Option<score::RoundResult>,
 }
 
 #[derive(Component)]
 pub struct TileSetupTimeline;
 
+/// Timeline of the placement tiles, rebuilt whenever the answer length changes.
+#[derive(Component)]
+pub struct PlacementSetupTimeline;
+
+/// Timeline that pulses a placement tile while the clip of its slot is playing.
+#[derive(Component)]
+pub struct PulseTimeline {
+    pub slot: usize,
+}
+
+/// Timeline of the answer reveal feedback on the placement tiles.
+#[derive(Component)]
+pub struct RevealTimeline;
+
+/// Where a placement tile rests once its setup animation is done.
+#[derive(Component)]
+pub struct RestTranslation(pub Vec3);
+
 #[derive(Component)]
 pub struct EmojiTile {
     pub index: usize,
@@ -40,6 +69,25 @@ pub struct EmojiTile {
 #[derive(Component)]
 pub struct EmojiMenuBtn;
 
+/// Icon entity shown on an emoji menu tile.
+#[derive(Component)]
+pub struct EmojiTileIcon(pub Entity);
+
+/// Page of emojis currently shown on the emoji menu.
+#[derive(Resource, Default)]
+pub struct EmojiPage {
+    pub current: usize,
+    pub count: usize,
+    /// Number of tiles in a single row of the emoji menu.
+    pub columns: usize,
+}
+
+#[derive(Component, Default)]
+pub struct PrevPageBtn;
+
+#[derive(Component, Default)]
+pub struct NextPageBtn;
+
 #[derive(Component)]
 pub struct PlacementMenuBtn;
 
@@ -49,14 +97,21 @@ pub struct PlacementIndex(pub usize);
 #[derive(Component, Default)]
 pub struct PlaySoundBtn;
 
+#[derive(Component, Default)]
+pub struct SubmitBtn;
+
 #[derive(Component, Default)]
 pub struct NextBtn;
 
 #[derive(Component, Default)]
 pub struct ExitBtn;
 
+/// Tags an entity that belongs to a single player of the [`Roster`](crate::players::Roster).
 #[derive(Component)]
-pub struct PlayerSelection;
+pub struct PlayerSelection {
+    /// Index into the roster.
+    pub player: usize,
+}
 
 #[derive(Component)]
 pub struct Menu;
@@ -64,36 +119,90 @@ pub struct Menu;
 const SPACING_SCALE: f32 = 3.0;
 const STARTING_SCALE: Vec3 = Vec3::splat(0.5);
 const LOTTIE_SCALE: Vec3 = Vec3::splat(0.05);
+/// Size of a placement tile when the answer is short enough to fit.
+const MAX_PLACEMENT_TILE_SIZE: f32 = 200.0;
+/// Maximum number of emojis shown on the emoji menu at once.
+pub const PAGE_SIZE: usize = 25;
+/// Seconds between two beats of a placement tile pulse.
+const PULSE_PERIOD: f32 = 0.5;
 
+/// Spawns one placement tile per emoji in the answer, replacing any existing ones.
+/// Runs again whenever [`GameSettings`] changes so the answer length can change at runtime.
 pub fn setup(
     mut commands: Commands,
     mut fragments: ResMut<Assets<VelloFragment>>,
+    q_placement_tiles: Query<Entity, With<PlacementMenuBtn>>,
+    q_placement_timelines: Query<(Entity, &Timeline), With<PlacementSetupTimeline>>,
+    q_reveal_timelines: Query<(Entity, &Timeline), With<RevealTimeline>>,
     mut emoji_guesses: ResMut<EmojiGuesses>,
+    mut random_number: ResMut<RandomNumber>,
+    mut ev_gen_rand_num: EventWriter<emoji::GenerateRandomNumber>,
+    settings: Res<GameSettings>,
+    game_state: Res<State<game::GameState>>,
 ) {
-    // Number of rows on the board
-    const ROW_COUNT: usize = 4;
-    // The size of a single tile
-    const TILE_SIZE: f32 = 200.0;
-    const HALF_TILE_SIZE: f32 = TILE_SIZE * 0.5;
+    // Number of tiles in the row
+    let row_count: usize = settings.answer_len();
+    // The size of a single tile, shrunk to keep long rows on screen
+    let tile_size: f32 = f32::min(MAX_PLACEMENT_TILE_SIZE, 800.0 / row_count as f32);
+    let half_tile_size: f32 = tile_size * 0.5;
 
     // The position where the tile should start at (both x and y axes)
-    const ROW_START: f32 = -(ROW_COUNT as f32 * TILE_SIZE) * 0.5 + HALF_TILE_SIZE;
+    let row_start: f32 = -(row_count as f32 * tile_size) * 0.5 + half_tile_size;
     const OFFSET: Vec3 = Vec3::new(0.0, 200.0, 0.0);
 
+    // Remove the previous row
+    for entity in q_placement_tiles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut time_scale: f32 = -1.0;
+    for (entity, timeline) in q_placement_timelines.iter() {
+        time_scale = timeline.time_scale;
+        if let Some(sequence_id) = timeline.sequence_id() {
+            commands.entity(sequence_id).despawn();
+        }
+        commands.entity(entity).despawn();
+    }
+
+    // Reveal animations target the tiles that were just removed
+    for (entity, timeline) in q_reveal_timelines.iter() {
+        if let Some(sequence_id) = timeline.sequence_id() {
+            commands.entity(sequence_id).despawn();
+        }
+        commands.entity(entity).despawn();
+    }
+
+    let board_visible: bool = matches!(
+        game_state.get(),
+        game::GameState::InGame | game::GameState::RoundReveal | game::GameState::Paused
+    );
+    if board_visible {
+        time_scale = 1.0;
+    }
+
+    *emoji_guesses = EmojiGuesses {
+        placement_tiles: Vec::with_capacity(row_count),
+        answer_tiles: Vec::with_capacity(row_count),
+        numbers: vec![-1; row_count],
+        tile_scale: tile_size / MAX_PLACEMENT_TILE_SIZE,
+        rivals: Vec::new(),
+    };
+
     // Color palette
     let palette: ColorPalette<ColorKey> = ColorPalette::default();
 
     let fill_color: Color = *palette.get_or_default(&ColorKey::Base0);
     let stroke_color: Color = *palette.get_or_default(&ColorKey::Base8);
-    let mut tile_sequences: Vec<Sequence> = Vec::with_capacity(ROW_COUNT);
+    let pulse_color: Color = *palette.get_or_default(&ColorKey::Yellow);
+    let mut tile_sequences: Vec<Sequence> = Vec::with_capacity(row_count);
 
-    for x in 0..ROW_COUNT {
+    for x in 0..row_count {
         // Spawn placement tiles
-        let translation: Vec3 = Vec3::new(TILE_SIZE * (x as f32) + ROW_START, 0.0, -1.0);
+        let translation: Vec3 = Vec3::new(tile_size * (x as f32) + row_start, 0.0, -1.0);
 
         let rect: VelloRectBundle = create_tile(
             &mut fragments,
-            TILE_SIZE as f64,
+            tile_size as f64,
             fill_color,
             stroke_color,
             translation,
@@ -102,20 +211,57 @@ pub fn setup(
             STARTING_SCALE,
         );
 
+        // Outline that pulses while the clip of this slot is playing
+        let pulse_rect: VelloRectBundle = VelloRectBundle {
+            rect: VelloRect::anchor_center(DVec2::splat(tile_size as f64), DVec4::splat(0.0)),
+            fill: FillStyle::from_brush(Color::NONE),
+            stroke: StrokeStyle::from_brush(pulse_color.with_a(0.0)).with_style(4.0),
+            fragment_bundle: VelloFragmentBundle {
+                fragment: fragments.add(VelloFragment::default()),
+                transform: TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, 0.5)),
+                ..default()
+            },
+        };
+        let mut pulse_id: Entity = Entity::PLACEHOLDER;
+
         let entity: Entity = commands
             .spawn((
                 rect.clone(),
                 EmojiTile { index: x },
-                Collider::cuboid(HALF_TILE_SIZE, HALF_TILE_SIZE),
+                RestTranslation(translation + OFFSET),
+                Collider::cuboid(half_tile_size, half_tile_size),
                 mouse::Clickable,
                 PlacementMenuBtn,
             ))
             .with_children(|parent| {
-                emoji_guesses.placement_tiles[x] = Some(parent.spawn_empty().id());
-                emoji_guesses.answer_tiles[x] = Some(parent.spawn_empty().id());
+                emoji_guesses
+                    .placement_tiles
+                    .push(Some(parent.spawn_empty().id()));
+                emoji_guesses
+                    .answer_tiles
+                    .push(Some(parent.spawn_empty().id()));
+                pulse_id = parent.spawn(pulse_rect.clone()).id();
             })
             .id();
 
+        let mut pulse_motion: VelloRectBundleMotion =
+            VelloRectBundleMotion::new(pulse_id, pulse_rect);
+
+        let mut act: ActionBuilder = ActionBuilder::new(&mut commands);
+        let pulse_sequence: Sequence = all(&[
+            act.play(pulse_motion.transform.scale_to(Vec3::splat(1.1)), 1.0),
+            act.play(pulse_motion.stroke.brush_to(pulse_color), 1.0),
+        ]);
+
+        // Parented to the tile so both go away when the row is rebuilt
+        let pulse_sequence_id: Entity = commands.spawn(pulse_sequence).id();
+        let pulse_timeline_id: Entity = commands
+            .spawn((Timeline::new(pulse_sequence_id), PulseTimeline { slot: x }))
+            .id();
+        commands
+            .entity(entity)
+            .push_children(&[pulse_sequence_id, pulse_timeline_id]);
+
         let mut rect_motion: VelloRectBundleMotion = VelloRectBundleMotion::new(entity, rect);
 
         // Tile initial animation
@@ -132,93 +278,150 @@ pub fn setup(
     let sequence_id: Entity = commands.spawn(sequence).id();
 
     let mut timeline: Timeline = Timeline::new(sequence_id);
-    timeline.time_scale = -1.0;
-    commands.spawn((timeline, SetupTimeline, TileSetupTimeline));
+    timeline.time_scale = time_scale;
+    commands.spawn((
+        timeline,
+        SetupTimeline,
+        TileSetupTimeline,
+        PlacementSetupTimeline,
+    ));
+
+    // Start a new round with the new answer length
+    random_number.numbers = vec![0; row_count];
+    if board_visible {
+        ev_gen_rand_num.send(emoji::GenerateRandomNumber);
+    }
 }
 
 pub fn setup_menu(
     mut commands: Commands,
     mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
     emoji_map: Res<EmojiMap>,
+    mut emoji_page: ResMut<EmojiPage>,
 ) {
-    // Number of rows on the board
-    const ROW_COUNT: usize = 5;
     // The size of a single tile
     const TILE_SIZE: f32 = 50.0;
     const HALF_TILE_SIZE: f32 = TILE_SIZE * 0.5;
-
-    // The position where the tile should start at (both x and y axes)
-    const ROW_START: f32 = -(ROW_COUNT as f32 * TILE_SIZE) * 0.5 + HALF_TILE_SIZE;
     const OFFSET: Vec3 = Vec3::new(0.0, -100.0, 0.0);
 
+    // Number of tiles needed on a single page
+    let tile_count: usize = usize::min(emoji_map.names.len(), PAGE_SIZE);
+
+    // Loading stops with an error before getting here without emojis, see `emoji::track_loading`
+    if tile_count == 0 {
+        *emoji_page = EmojiPage::default();
+        error!("No emojis to fill the emoji menu with");
+        return;
+    }
+
+    let column_count: usize = (tile_count as f32).sqrt().ceil() as usize;
+    let row_count: usize = (tile_count + column_count - 1) / column_count;
+
+    // The position where the tile should start at for each axis
+    let column_start: f32 = -(column_count as f32 * TILE_SIZE) * 0.5 + HALF_TILE_SIZE;
+    let row_start: f32 = -(row_count as f32 * TILE_SIZE) * 0.5 + HALF_TILE_SIZE;
+
+    emoji_page.current = 0;
+    emoji_page.count = (emoji_map.names.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+    emoji_page.columns = column_count;
+
     // Color palette
     let palette: ColorPalette<ColorKey> = ColorPalette::default();
 
     let stroke_color: Color = *palette.get_or_default(&ColorKey::Base8);
     let fill_color: Color = *palette.get_or_default(&ColorKey::Base2);
-    let mut tile_sequences: Vec<Sequence> = Vec::with_capacity(ROW_COUNT);
-
-    for x in 0..ROW_COUNT {
-        for y in 0..ROW_COUNT {
-            // Spawn board tiles
-            let translation: Vec3 = Vec3::new(
-                TILE_SIZE * (x as f32) + ROW_START,
-                TILE_SIZE * (y as f32) + ROW_START,
-                -1.0,
-            );
+    let mut tile_sequences: Vec<Sequence> = Vec::with_capacity(tile_count * 2);
 
-            let rect: VelloRectBundle = create_tile(
-                &mut fragments,
-                TILE_SIZE as f64,
-                fill_color,
-                stroke_color,
-                translation,
-                OFFSET,
-                SPACING_SCALE,
-                STARTING_SCALE,
-            );
+    for index in 0..tile_count {
+        let x: usize = index % column_count;
+        let y: usize = index / column_count;
 
-            let index: usize = x + y * ROW_COUNT;
-            let mut icon_id: Option<Entity> = None;
-            let entity: Entity = commands
-                .spawn((
-                    rect.clone(),
-                    EmojiTile { index },
-                    Collider::cuboid(HALF_TILE_SIZE, HALF_TILE_SIZE),
-                    mouse::Clickable,
-                    EmojiMenuBtn,
-                ))
-                .with_children(|parent| {
-                    icon_id = Some(
-                        parent
-                            .spawn(bevy_vello::VelloVectorBundle {
-                                vector: emoji_map.data[index].vector_handle.clone(),
-                                transform: Transform::from_xyz(0.0, -TILE_SIZE * 0.5, 1.0)
-                                    .with_scale(Vec3::splat(0.0)),
-                                ..default()
-                            })
-                            .id(),
-                    );
-                })
-                .id();
+        // Spawn board tiles
+        let translation: Vec3 = Vec3::new(
+            TILE_SIZE * (x as f32) + column_start,
+            TILE_SIZE * (y as f32) + row_start,
+            -1.0,
+        );
 
-            let mut rect_motion: VelloRectBundleMotion = VelloRectBundleMotion::new(entity, rect);
-            let mut icon_motion: TransformMotion = TransformMotion::new(
-                icon_id.unwrap(),
-                Transform::from_xyz(0.0, -TILE_SIZE * 0.5, 1.0).with_scale(Vec3::splat(0.0)),
-            );
+        let rect: VelloRectBundle = create_tile(
+            &mut fragments,
+            TILE_SIZE as f64,
+            fill_color,
+            stroke_color,
+            translation,
+            OFFSET,
+            SPACING_SCALE,
+            STARTING_SCALE,
+        );
 
-            // Tile initial animation
-            tile_sequences.push(create_tile_animation(
-                &mut commands,
-                &mut rect_motion,
-                translation + OFFSET,
-                fill_color,
-                stroke_color,
-            ));
-            let mut act: ActionBuilder = ActionBuilder::new(&mut commands);
-            tile_sequences.push(act.play(icon_motion.scale_to(LOTTIE_SCALE), 1.0));
-        }
+        let icon_id: Entity = commands
+            .spawn(bevy_vello::VelloVectorBundle {
+                vector: emoji_map.data[index].vector_handle.clone(),
+                transform: Transform::from_xyz(0.0, -TILE_SIZE * 0.5, 1.0)
+                    .with_scale(Vec3::splat(0.0)),
+                ..default()
+            })
+            .id();
+
+        let entity: Entity = commands
+            .spawn((
+                rect.clone(),
+                EmojiTile { index },
+                EmojiTileIcon(icon_id),
+                Collider::cuboid(HALF_TILE_SIZE, HALF_TILE_SIZE),
+                mouse::Clickable,
+                EmojiMenuBtn,
+            ))
+            .add_child(icon_id)
+            .id();
+
+        let mut rect_motion: VelloRectBundleMotion = VelloRectBundleMotion::new(entity, rect);
+        let mut icon_motion: TransformMotion = TransformMotion::new(
+            icon_id,
+            Transform::from_xyz(0.0, -TILE_SIZE * 0.5, 1.0).with_scale(Vec3::splat(0.0)),
+        );
+
+        // Tile initial animation
+        tile_sequences.push(create_tile_animation(
+            &mut commands,
+            &mut rect_motion,
+            translation + OFFSET,
+            fill_color,
+            stroke_color,
+        ));
+        let mut act: ActionBuilder = ActionBuilder::new(&mut commands);
+        tile_sequences.push(act.play(icon_motion.scale_to(LOTTIE_SCALE), 1.0));
+    }
+
+    // Page buttons are only needed when the emojis do not fit on a single page
+    if emoji_page.count > 1 {
+        let page_color: Color = *palette.get_or_default(&ColorKey::Base6);
+        let page_offset: f32 = column_count as f32 * TILE_SIZE * 0.5 + 80.0;
+
+        tile_sequences.push(menu_ui::create_button::<PrevPageBtn>(
+            &mut commands,
+            &mut fragments,
+            &mut typst_compiler,
+            DVec2::new(70.0, 70.0),
+            100.0,
+            page_color,
+            Vec3::new(-page_offset, -200.0, 0.0) + OFFSET,
+            Vec3::Y * 100.0,
+            "= \\<",
+        ));
+
+        tile_sequences.push(menu_ui::create_button::<NextPageBtn>(
+            &mut commands,
+            &mut fragments,
+            &mut typst_compiler,
+            DVec2::new(70.0, 70.0),
+            100.0,
+            page_color,
+            Vec3::new(page_offset, -200.0, 0.0) + OFFSET,
+            Vec3::Y * 100.0,
+            "= \\>",
+        ));
     }
 
     let sequence: Sequence = flow(0.02, &tile_sequences).with_ease(ease::cubic::ease_in_out);
@@ -229,6 +432,48 @@ pub fn setup_menu(
     commands.spawn((timeline, SetupTimeline, TileSetupTimeline));
 }
 
+pub fn page_btn_evt(
+    q_prev_btn: Query<With<PrevPageBtn>>,
+    q_next_btn: Query<With<NextPageBtn>>,
+    mut q_emoji_tiles: Query<(&mut EmojiTile, &EmojiTileIcon, &mut Visibility), With<EmojiMenuBtn>>,
+    mut q_icons: Query<&mut Handle<VelloVector>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut emoji_page: ResMut<EmojiPage>,
+    emoji_map: Res<EmojiMap>,
+    hint_budget: Res<HintBudget>,
+) {
+    for clicked in ev_clicked.read() {
+        let page: usize = if q_prev_btn.contains(clicked.entity) {
+            (emoji_page.current + emoji_page.count - 1) % emoji_page.count
+        } else if q_next_btn.contains(clicked.entity) {
+            (emoji_page.current + 1) % emoji_page.count
+        } else {
+            continue;
+        };
+
+        for (mut emoji_tile, icon, mut visibility) in q_emoji_tiles.iter_mut() {
+            let slot: usize = emoji_tile.index % PAGE_SIZE;
+            emoji_tile.index = page * PAGE_SIZE + slot;
+
+            // The last page might not be full
+            let Some(data) = emoji_map.data.get(emoji_tile.index) else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+
+            *visibility = match hint_budget.removed.contains(&emoji_tile.index) {
+                true => Visibility::Hidden,
+                false => Visibility::Inherited,
+            };
+            if let Ok(mut vector) = q_icons.get_mut(icon.0) {
+                *vector = data.vector_handle.clone();
+            }
+        }
+
+        emoji_page.current = page;
+    }
+}
+
 pub fn setup_action_btn(
     mut commands: Commands,
     mut fragments: ResMut<Assets<VelloFragment>>,
@@ -248,6 +493,18 @@ pub fn setup_action_btn(
         "= \\~ 🎵",
     );
 
+    let submit_btn_seq: Sequence = menu_ui::create_button::<SubmitBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 70.0),
+        100.0,
+        *palette.get_or_default(&ColorKey::Green),
+        Vec3::new(500.0, 0.0, 0.0),
+        Vec3::Y * 100.0,
+        "= Submit",
+    );
+
     let next_btn_seq: Sequence = menu_ui::create_button::<NextBtn>(
         &mut commands,
         &mut fragments,
@@ -272,8 +529,11 @@ pub fn setup_action_btn(
         "= Exit",
     );
 
-    let sequence: Sequence =
-        flow(0.1, &[play_btn_seq, next_btn_seq, exit_btn_seq]).with_ease(ease::cubic::ease_in_out);
+    let sequence: Sequence = flow(
+        0.1,
+        &[submit_btn_seq, play_btn_seq, next_btn_seq, exit_btn_seq],
+    )
+    .with_ease(ease::cubic::ease_in_out);
     let sequence_id: Entity = commands.spawn(sequence).id();
 
     let mut timeline: Timeline = Timeline::new(sequence_id);
@@ -285,72 +545,375 @@ pub fn play_sound_button_evt(
     q_play_sound_btns: Query<&PlaySoundBtn>,
     mut ev_clicked: EventReader<mouse::Clicked>,
     mut ev_play_sound: EventWriter<emoji::PlaySound>,
+    mut hint_budget: ResMut<HintBudget>,
 ) {
     for clicked in ev_clicked.read() {
         if let Ok(_) = q_play_sound_btns.get(clicked.entity) {
-            ev_play_sound.send(emoji::PlaySound);
+            if hint_budget.replays_left == 0 {
+                continue;
+            }
+
+            hint_budget.replays_left -= 1;
+            ev_play_sound.send(emoji::PlaySound { slot: None });
         }
     }
 }
 
+/// Clicking a filled placement tile clears it, clicking an empty one targets it for the next pick.
+/// Dragging a guess onto another placement tile swaps the two.
 pub fn placement_tiles_evt(
+    mut commands: Commands,
     q_placement_tile: Query<&EmojiTile, With<PlacementMenuBtn>>,
-    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut ev_released: EventReader<mouse::Released>,
     mut placement_index: ResMut<PlacementIndex>,
+    mut guesses: ResMut<EmojiGuesses>,
+    emoji_map: Res<EmojiMap>,
+    party_round: Res<party::PartyRound>,
 ) {
-    for clicked in ev_clicked.read() {
-        if let Ok(placement_tile) = q_placement_tile.get(clicked.entity) {
-            placement_index.0 = placement_tile.index;
+    // Locked in guesses can no longer be changed
+    if party_round.lock_order.contains(&0) {
+        ev_released.clear();
+        return;
+    }
+
+    for released in ev_released.read() {
+        let Ok(placement_tile) = q_placement_tile.get(released.entity) else {
+            continue;
+        };
+        let slot: usize = placement_tile.index;
+
+        if released.is_click() {
+            if guesses.numbers[slot] != -1 {
+                remove_guess(&mut commands, &mut guesses, slot);
+            }
+            placement_index.0 = slot;
+            continue;
+        }
+
+        let Some(from) = released
+            .pressed
+            .iter()
+            .find_map(|&entity| q_placement_tile.get(entity).ok())
+            .map(|tile| tile.index)
+        else {
+            continue;
+        };
+
+        let dragged: i32 = guesses.numbers[from];
+        if dragged == -1 {
+            continue;
+        }
+
+        // Swap with whatever the target slot holds
+        let target: i32 = guesses.numbers[slot];
+        remove_guess(&mut commands, &mut guesses, from);
+        if target != -1 {
+            place_guess(
+                &mut commands,
+                &mut guesses,
+                &emoji_map,
+                from,
+                target as usize,
+            );
         }
+        place_guess(
+            &mut commands,
+            &mut guesses,
+            &emoji_map,
+            slot,
+            dragged as usize,
+        );
     }
 }
 
+/// Places the picked emoji on the targeted placement tile, or the first empty one.
 pub fn emoji_tiles_evt(
     mut commands: Commands,
     q_emoji_tiles: Query<&EmojiTile, With<EmojiMenuBtn>>,
     mut ev_clicked: EventReader<mouse::Clicked>,
     mut guesses: ResMut<EmojiGuesses>,
+    mut placement_index: ResMut<PlacementIndex>,
     emoji_map: Res<EmojiMap>,
-    random_number: Res<RandomNumber>,
+    hint_budget: Res<HintBudget>,
+    party_round: Res<party::PartyRound>,
 ) {
     for clicked in ev_clicked.read() {
+        // Other players fill their own rows, see `party::rival_pick_evt`
+        if clicked.player != 0 || party_round.lock_order.contains(&0) {
+            continue;
+        }
+
         if let Ok(emoji_tile) = q_emoji_tiles.get(clicked.entity) {
-            if array_contain_number(&guesses.numbers, emoji_tile.index as i32) {
+            // Tile is hidden on the last page or removed by a hint
+            if emoji_tile.index >= emoji_map.data.len()
+                || hint_budget.removed.contains(&emoji_tile.index)
+                || array_contain_number(&guesses.numbers, emoji_tile.index as i32)
+            {
+                continue;
+            }
+
+            let Some(slot) = Some(placement_index.0)
+                .filter(|&slot| guesses.numbers.get(slot) == Some(&-1))
+                .or_else(|| guesses.numbers.iter().position(|&n| n == -1))
+            else {
+                continue;
+            };
+
+            println!("guess index: {}", emoji_tile.index);
+            place_guess(
+                &mut commands,
+                &mut guesses,
+                &emoji_map,
+                slot,
+                emoji_tile.index,
+            );
+
+            // Target the next empty slot, starting over once the row is full
+            placement_index.0 = guesses.numbers.iter().position(|&n| n == -1).unwrap_or(0);
+        }
+    }
+}
+
+/// Reveals the answer once every placement tile holds a guess.
+/// When players guess at the same time the guesses are only locked in.
+pub fn submit_btn_evt(
+    q_submit_btn: Query<With<SubmitBtn>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut ev_lock_in: EventWriter<party::LockIn>,
+    mut next_game_state: ResMut<NextState<game::GameState>>,
+    guesses: Res<EmojiGuesses>,
+    roster: Res<Roster>,
+) {
+    for clicked in ev_clicked.read() {
+        if q_submit_btn.contains(clicked.entity)
+            && array_contain_number(&guesses.numbers, -1) == false
+        {
+            match roster.simultaneous {
+                true => ev_lock_in.send(party::LockIn { player: 0 }),
+                false => next_game_state.set(game::GameState::RoundReveal),
+            }
+        }
+    }
+}
+
+/// Plays the clip of a single emoji on right click without guessing it,
+/// so it can be compared against the answer.
+pub fn preview_emoji_evt(
+    mut commands: Commands,
+    q_emoji_tiles: Query<&EmojiTile, With<EmojiMenuBtn>>,
+    q_previews: Query<Entity, With<emoji::EmojiPreview>>,
+    mut ev_right_clicked: EventReader<mouse::RightClicked>,
+    emoji_map: Res<EmojiMap>,
+    hint_budget: Res<HintBudget>,
+) {
+    for right_clicked in ev_right_clicked.read() {
+        let Ok(emoji_tile) = q_emoji_tiles.get(right_clicked.entity) else {
+            continue;
+        };
+
+        // Tile is hidden on the last page or removed by a hint
+        let Some(data) = emoji_map.data.get(emoji_tile.index) else {
+            continue;
+        };
+        if hint_budget.removed.contains(&emoji_tile.index) {
+            continue;
+        }
+
+        for entity in q_previews.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        commands.spawn((
+            AudioBundle {
+                source: data.audio_handle.clone(),
+                settings: PlaybackSettings::DESPAWN,
+            },
+            emoji::EmojiPreview,
+        ));
+    }
+}
+
+/// Tints each placement tile by how well it matched, pops the answer in above it
+/// and shakes the tiles that were guessed wrong.
+pub fn reveal_answer(
+    mut commands: Commands,
+    q_placement_tiles: Query<
+        (
+            Entity,
+            &EmojiTile,
+            &VelloRect,
+            &RestTranslation,
+            &Handle<VelloFragment>,
+        ),
+        With<PlacementMenuBtn>,
+    >,
+    q_reveal_timelines: Query<(Entity, &Timeline), With<RevealTimeline>>,
+    mut ev_round_scored: EventReader<score::RoundScored>,
+    guesses: Res<EmojiGuesses>,
+    emoji_map: Res<EmojiMap>,
+) {
+    const SHAKE_DISTANCE: f32 = 10.0;
+
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+    let rest_fill_color: Color = *palette.get_or_default(&ColorKey::Base0);
+    let stroke_color: Color = *palette.get_or_default(&ColorKey::Base8);
+
+    for round_scored in ev_round_scored.read() {
+        // The previous reveal might still be rewinding, the new one starts from the tiles at rest
+        for (entity, timeline) in q_reveal_timelines.iter() {
+            if let Some(sequence_id) = timeline.sequence_id() {
+                commands.entity(sequence_id).despawn();
+            }
+            commands.entity(entity).despawn();
+        }
+
+        let tile_scale: f32 = guesses.tile_scale;
+        let mut tile_sequences: Vec<Sequence> = Vec::with_capacity(guesses.answer_tiles.len());
+
+        for (entity, emoji_tile, rect, rest, fragment) in q_placement_tiles.iter() {
+            let t: usize = emoji_tile.index;
+            let (Some(&slot_result), Some(Some(answer_id))) = (
+                round_scored.result.slots.get(t),
+                guesses.answer_tiles.get(t),
+            ) else {
                 continue;
+            };
+
+            let fill_color: Color = match slot_result {
+                score::SlotResult::Exact => *palette.get_or_default(&ColorKey::Green),
+                score::SlotResult::Partial => *palette.get_or_default(&ColorKey::Yellow),
+                score::SlotResult::Miss => *palette.get_or_default(&ColorKey::Red),
+            };
+
+            let answer_transform: Transform =
+                Transform::from_xyz(0.0, 20.0 * tile_scale, 1.0).with_scale(Vec3::splat(0.0));
+            commands
+                .entity(*answer_id)
+                .insert(bevy_vello::VelloVectorBundle {
+                    vector: emoji_map.data[round_scored.result.answer[t]]
+                        .vector_handle
+                        .clone(),
+                    transform: answer_transform,
+                    ..default()
+                });
+
+            let rect: VelloRectBundle = VelloRectBundle {
+                rect: rect.clone(),
+                fill: FillStyle::from_brush(rest_fill_color),
+                stroke: StrokeStyle::from_brush(stroke_color).with_style(2.0),
+                fragment_bundle: VelloFragmentBundle {
+                    fragment: fragment.clone(),
+                    transform: TransformBundle::from_transform(Transform::from_translation(rest.0)),
+                    ..default()
+                },
+            };
+            let mut rect_motion: VelloRectBundleMotion = VelloRectBundleMotion::new(entity, rect);
+            let mut answer_motion: TransformMotion =
+                TransformMotion::new(*answer_id, answer_transform);
+
+            let tint_seq: Sequence = create_tile_animation(
+                &mut commands,
+                &mut rect_motion,
+                rest.0,
+                fill_color,
+                stroke_color,
+            );
+
+            let mut act: ActionBuilder = ActionBuilder::new(&mut commands);
+            let pop_seq: Sequence =
+                act.play(answer_motion.scale_to(Vec3::splat(0.08 * tile_scale)), 1.0);
+
+            if slot_result == score::SlotResult::Exact {
+                tile_sequences.push(all(&[tint_seq, pop_seq]));
+            } else {
+                let shake_seq: Sequence = chain(&[
+                    act.play(
+                        rect_motion
+                            .transform
+                            .translate_add(Vec3::X * SHAKE_DISTANCE),
+                        0.1,
+                    ),
+                    act.play(
+                        rect_motion
+                            .transform
+                            .translate_add(Vec3::X * -SHAKE_DISTANCE * 2.0),
+                        0.2,
+                    ),
+                    act.play(
+                        rect_motion
+                            .transform
+                            .translate_add(Vec3::X * SHAKE_DISTANCE * 2.0),
+                        0.2,
+                    ),
+                    act.play(
+                        rect_motion
+                            .transform
+                            .translate_add(Vec3::X * -SHAKE_DISTANCE),
+                        0.1,
+                    ),
+                ]);
+                tile_sequences.push(chain(&[all(&[tint_seq, pop_seq]), shake_seq]));
             }
+        }
+
+        let sequence: Sequence = flow(0.2, &tile_sequences).with_ease(ease::cubic::ease_in_out);
+        let sequence_id: Entity = commands.spawn(sequence).id();
 
-            for n in 0..guesses.numbers.len() {
-                if guesses.numbers[n] == -1 {
-                    println!("guess index: {}", emoji_tile.index);
-                    guesses.numbers[n] = emoji_tile.index as i32;
-
-                    commands.entity(guesses.placement_tiles[n].unwrap()).insert(
-                        bevy_vello::VelloVectorBundle {
-                            vector: emoji_map.data[emoji_tile.index].vector_handle.clone(),
-                            transform: Transform::from_xyz(0.0, -50.0, 1.0)
-                                .with_scale(Vec3::splat(0.1)),
-                            ..default()
-                        },
-                    );
-
-                    // last guess
-                    if n == 3 {
-                        for t in 0..guesses.placement_tiles.len() {
-                            commands.entity(guesses.answer_tiles[t].unwrap()).insert(
-                                bevy_vello::VelloVectorBundle {
-                                    vector: emoji_map.data[random_number.numbers[t]]
-                                        .vector_handle
-                                        .clone(),
-                                    transform: Transform::from_xyz(0.0, 20.0, 1.0)
-                                        .with_scale(Vec3::splat(0.08)),
-                                    ..default()
-                                },
-                            );
-                        }
-                    }
-                    break;
-                }
+        let mut timeline: Timeline = Timeline::new(sequence_id);
+        timeline.time_scale = 1.0;
+        commands.spawn((timeline, SetupTimeline, RevealTimeline));
+    }
+}
+
+/// Pulses each placement tile while the clip of its slot is playing, fading out as the clip ends.
+pub fn pulse_playing_tiles(
+    mut q_pulse_timelines: Query<(&mut Timeline, &PulseTimeline)>,
+    q_emoji_audio: Query<(&emoji::EmojiAudio, &AudioSink)>,
+    audio_schedule: Res<emoji::AudioSchedule>,
+) {
+    for (mut timeline, pulse) in q_pulse_timelines.iter_mut() {
+        let playing: bool = q_emoji_audio
+            .iter()
+            .any(|(emoji_audio, sink)| emoji_audio.slot == pulse.slot && sink.empty() == false);
+
+        let clip: Option<&emoji::ScheduledClip> = audio_schedule
+            .clips
+            .iter()
+            .find(|clip| clip.slot == pulse.slot)
+            .filter(|clip| playing && clip.duration > 0.0);
+
+        let Some(clip) = clip else {
+            timeline.target_time = 0.0;
+            continue;
+        };
+
+        let clip_time: f32 = audio_schedule.elapsed - clip.start;
+        let progress: f32 = (clip_time / clip.duration).clamp(0.0, 1.0);
+        // Triangle wave that peaks halfway through every beat
+        let beat: f32 = 1.0 - ((clip_time / PULSE_PERIOD).fract() * 2.0 - 1.0).abs();
+
+        timeline.target_time = beat * (1.0 - progress);
+    }
+}
+
+/// Plays the reveal backwards so the placement tiles return to their original look.
+pub fn rewind_reveal(mut q_reveal_timelines: Query<&mut Timeline, With<RevealTimeline>>) {
+    for mut timeline in q_reveal_timelines.iter_mut() {
+        timeline.time_scale = -2.0;
+    }
+}
+
+/// Despawns reveal timelines that finished rewinding.
+pub fn cleanup_reveal(
+    mut commands: Commands,
+    q_reveal_timelines: Query<(Entity, &Timeline), With<RevealTimeline>>,
+) {
+    for (entity, timeline) in q_reveal_timelines.iter() {
+        if timeline.time_scale < 0.0 && timeline.target_time <= 0.0 {
+            if let Some(sequence_id) = timeline.sequence_id() {
+                commands.entity(sequence_id).despawn();
             }
+            commands.entity(entity).despawn();
         }
     }
 }
@@ -364,28 +927,77 @@ pub fn array_contain_number<T: Eq + PartialEq>(array: &[T], number: T) -> bool {
     false
 }
 
+/// Shows an emoji on a placement tile and records it as the guess of that slot.
+pub fn place_guess(
+    commands: &mut Commands,
+    guesses: &mut EmojiGuesses,
+    emoji_map: &EmojiMap,
+    slot: usize,
+    index: usize,
+) {
+    guesses.numbers[slot] = index as i32;
+
+    let tile_scale: f32 = guesses.tile_scale;
+    commands
+        .entity(guesses.placement_tiles[slot].unwrap())
+        .insert(bevy_vello::VelloVectorBundle {
+            vector: emoji_map.data[index].vector_handle.clone(),
+            transform: Transform::from_xyz(0.0, -50.0 * tile_scale, 1.0)
+                .with_scale(Vec3::splat(0.1 * tile_scale)),
+            ..default()
+        });
+}
+
+/// Empties a single placement tile.
+pub fn remove_guess(commands: &mut Commands, guesses: &mut EmojiGuesses, slot: usize) {
+    guesses.numbers[slot] = -1;
+
+    commands
+        .entity(guesses.placement_tiles[slot].unwrap())
+        .remove::<Handle<bevy_vello::VelloVector>>();
+}
+
+/// Removes every guess and revealed answer from the placement tiles.
+pub fn clear_guesses(commands: &mut Commands, guesses: &mut EmojiGuesses) {
+    for t in 0..guesses.placement_tiles.len() {
+        guesses.numbers[t] = -1;
+
+        commands
+            .entity(guesses.placement_tiles[t].unwrap())
+            .remove::<Handle<bevy_vello::VelloVector>>();
+
+        commands
+            .entity(guesses.answer_tiles[t].unwrap())
+            .remove::<Handle<bevy_vello::VelloVector>>();
+    }
+}
+
 pub fn next_btn_evt(
     mut commands: Commands,
     q_next_btn: Query<With<NextBtn>>,
     mut ev_clicked: EventReader<mouse::Clicked>,
     mut ev_gen_rand_num: EventWriter<emoji::GenerateRandomNumber>,
     mut guesses: ResMut<EmojiGuesses>,
+    mut next_game_state: ResMut<NextState<game::GameState>>,
+    score: Res<score::Score>,
+    settings: Res<GameSettings>,
+    lan: Res<Lan>,
 ) {
     for clicked in ev_clicked.read() {
-        if let Ok(_) = q_next_btn.get(clicked.entity) {
-            for t in 0..guesses.placement_tiles.len() {
-                guesses.numbers[t] = -1;
-
-                commands
-                    .entity(guesses.placement_tiles[t].unwrap())
-                    .remove::<Handle<bevy_vello::VelloVector>>();
+        // The host moves every client to the next round
+        if lan.is_client() {
+            continue;
+        }
 
-                commands
-                    .entity(guesses.answer_tiles[t].unwrap())
-                    .remove::<Handle<bevy_vello::VelloVector>>();
+        if let Ok(_) = q_next_btn.get(clicked.entity) {
+            if score.rounds.len() >= settings.rounds_per_game {
+                next_game_state.set(game::GameState::GameOver);
+                continue;
             }
 
+            clear_guesses(&mut commands, &mut guesses);
             ev_gen_rand_num.send(emoji::GenerateRandomNumber);
+            next_game_state.set(game::GameState::InGame);
         }
     }
 }
@@ -393,11 +1005,11 @@ pub fn next_btn_evt(
 pub fn exit_btn_evt(
     q_exit_btn: Query<With<ExitBtn>>,
     mut ev_clicked: EventReader<mouse::Clicked>,
-    mut game_state: ResMut<game::GameStateRes>,
+    mut next_game_state: ResMut<NextState<game::GameState>>,
 ) {
     for clicked in ev_clicked.read() {
         if let Ok(_) = q_exit_btn.get(clicked.entity) {
-            game_state.target_state = game::GameState::Start;
+            next_game_state.set(game::GameState::Menu);
         }
     }
 }
@@ -1,23 +1,57 @@
-use bevy::prelude::*;
+use bevy::{app::AppExit, prelude::*};
 use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+use serde::{Deserialize, Serialize};
 
-use crate::{emoji_ui, menu_ui};
+use crate::{emoji, emoji_ui, menu_ui, mouse};
 
-#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[derive(States, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
 pub enum GameState {
     #[default]
-    Start,
+    Loading,
+    Menu,
     InGame,
-    End,
+    RoundReveal,
+    GameOver,
+    Paused,
+    /// Playing the menu outro before the app exits.
+    Quitting,
 }
 
+/// State to return to when leaving [`GameState::Paused`].
 #[derive(Resource, Default)]
-pub struct GameStateRes {
-    pub curr_state: GameState,
-    pub target_state: GameState,
+pub struct PausedFrom(pub GameState);
+
+/// Marker for the text shown while the game is paused.
+#[derive(Component)]
+pub struct PauseOverlay;
+
+pub fn show_menu(
+    mut q_emoji_ui_setup: Query<
+        &mut Timeline,
+        (
+            With<emoji_ui::TileSetupTimeline>,
+            Without<menu_ui::MenuSetupTimeline>,
+        ),
+    >,
+    mut q_menu_ui_setup: Query<
+        &mut Timeline,
+        (
+            With<menu_ui::MenuSetupTimeline>,
+            Without<emoji_ui::TileSetupTimeline>,
+        ),
+    >,
+) {
+    for mut emoji_ui_setup in q_emoji_ui_setup.iter_mut() {
+        emoji_ui_setup.time_scale = -1.0;
+    }
+
+    for mut menu_ui_setup in q_menu_ui_setup.iter_mut() {
+        menu_ui_setup.time_scale = 1.0;
+    }
 }
 
-pub fn game_manager(
+pub fn show_board(
     mut q_emoji_ui_setup: Query<
         &mut Timeline,
         (
@@ -32,36 +66,123 @@ pub fn game_manager(
             Without<emoji_ui::TileSetupTimeline>,
         ),
     >,
-    mut game_state: ResMut<GameStateRes>,
 ) {
-    // Game state already achieved
-    if game_state.curr_state == game_state.target_state {
-        return;
+    for mut emoji_ui_setup in q_emoji_ui_setup.iter_mut() {
+        emoji_ui_setup.time_scale = 1.0;
+    }
+
+    for mut menu_ui_setup in q_menu_ui_setup.iter_mut() {
+        menu_ui_setup.time_scale = -1.0;
     }
+}
 
-    println!("Game state changes to: {:#?}", game_state.target_state);
-    match game_state.target_state {
-        GameState::Start => {
-            for mut emoji_ui_setup in q_emoji_ui_setup.iter_mut() {
-                emoji_ui_setup.time_scale = -1.0;
-            }
+pub fn hide_board(mut q_emoji_ui_setup: Query<&mut Timeline, With<emoji_ui::TileSetupTimeline>>) {
+    for mut emoji_ui_setup in q_emoji_ui_setup.iter_mut() {
+        emoji_ui_setup.time_scale = -1.0;
+    }
+}
 
-            for mut menu_ui_setup in q_menu_ui_setup.iter_mut() {
-                menu_ui_setup.time_scale = 1.0;
-            }
-        }
-        GameState::InGame => {
-            for mut emoji_ui_setup in q_emoji_ui_setup.iter_mut() {
-                emoji_ui_setup.time_scale = 1.0;
-            }
-
-            for mut menu_ui_setup in q_menu_ui_setup.iter_mut() {
-                menu_ui_setup.time_scale = -1.0;
-            }
+/// Clears the board from a previous game and generates the first round.
+pub fn new_game(
+    mut commands: Commands,
+    mut guesses: ResMut<emoji_ui::EmojiGuesses>,
+    mut ev_gen_rand_num: EventWriter<emoji::GenerateRandomNumber>,
+) {
+    emoji_ui::clear_guesses(&mut commands, &mut guesses);
+    ev_gen_rand_num.send(emoji::GenerateRandomNumber);
+}
+
+/// Toggles [`GameState::Paused`] with the escape key, or quits from the main menu.
+pub fn escape_input(
+    input_keyboard: Res<Input<KeyCode>>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut paused_from: ResMut<PausedFrom>,
+) {
+    if input_keyboard.just_pressed(KeyCode::Escape) == false {
+        return;
+    }
+
+    match game_state.get() {
+        GameState::InGame | GameState::RoundReveal => {
+            paused_from.0 = *game_state.get();
+            next_game_state.set(GameState::Paused);
         }
-        GameState::End => {}
+        GameState::Paused => next_game_state.set(paused_from.0),
+        GameState::Menu => next_game_state.set(GameState::Quitting),
+        _ => {}
+    }
+}
+
+pub fn enter_pause(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_audio_sinks: Query<&AudioSink>,
+) {
+    for sink in q_audio_sinks.iter() {
+        sink.pause();
+    }
+
+    let header: String = r###"
+        #set page(width: 300pt, margin: 8pt)
+        #set text(size: 48pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    let Ok(label_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + "= Paused")
+    else {
+        return;
+    };
+
+    commands.entity(label_tree.root_entity).insert((
+        Transform::from_xyz(-label_tree.size.x * 0.5, label_tree.size.y * 0.5, 10.0),
+        PauseOverlay,
+    ));
+}
+
+pub fn exit_pause(
+    mut commands: Commands,
+    q_pause_overlay: Query<Entity, With<PauseOverlay>>,
+    q_audio_sinks: Query<&AudioSink>,
+) {
+    for sink in q_audio_sinks.iter() {
+        sink.play();
+    }
+
+    for entity in q_pause_overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Plays the menu outro in reverse.
+pub fn hide_menu(mut q_menu_ui_setup: Query<&mut Timeline, With<menu_ui::MenuSetupTimeline>>) {
+    for mut menu_ui_setup in q_menu_ui_setup.iter_mut() {
+        menu_ui_setup.time_scale = -1.0;
     }
+}
 
-    // Update curr state to target state
-    game_state.curr_state = game_state.target_state;
+/// Exits the app once the menu outro is done.
+pub fn exit_when_hidden(
+    q_menu_ui_setup: Query<&Timeline, With<menu_ui::MenuSetupTimeline>>,
+    mut ev_app_exit: EventWriter<AppExit>,
+) {
+    if q_menu_ui_setup
+        .iter()
+        .all(|menu_ui_setup| menu_ui_setup.target_time <= 0.0)
+    {
+        ev_app_exit.send(AppExit);
+    }
+}
+
+/// Drops clicks from the previous screen so they cannot leak into the new one.
+pub fn clear_input(
+    mut ev_clicked: ResMut<Events<mouse::Clicked>>,
+    mut ev_right_clicked: ResMut<Events<mouse::RightClicked>>,
+    mut ev_released: ResMut<Events<mouse::Released>>,
+) {
+    ev_clicked.clear();
+    ev_right_clicked.clear();
+    ev_released.clear();
 }
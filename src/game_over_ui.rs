@@ -0,0 +1,168 @@
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+
+use crate::daily::{self, DailyChallenge};
+use crate::emoji::EmojiMap;
+use crate::game::GameState;
+use crate::players::Roster;
+use crate::score::{EmojiAccuracy, Score};
+use crate::seed::RoundSeed;
+use crate::{menu_ui, mouse, SetupTimeline};
+
+#[derive(Component, Default)]
+pub struct PlayAgainBtn;
+
+#[derive(Component, Default)]
+pub struct MainMenuBtn;
+
+/// Marker for everything spawned on the game over screen.
+#[derive(Component)]
+pub struct GameOverUi;
+
+pub fn setup(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    score: Res<Score>,
+    emoji_map: Res<EmojiMap>,
+    round_seed: Res<RoundSeed>,
+    daily: Res<DailyChallenge>,
+    roster: Res<Roster>,
+) {
+    const OFFSET: Vec3 = Vec3::new(0.0, 100.0, 0.0);
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+
+    // Results, most guessed emoji first
+    let mut accuracies: Vec<(&usize, &EmojiAccuracy)> = score.accuracy.iter().collect();
+    accuracies.sort_by(|a, b| (b.1.exact, b.1.seen).cmp(&(a.1.exact, a.1.seen)));
+
+    let mut accuracy_rows: String = String::new();
+    for (&index, accuracy) in accuracies {
+        let Some(data) = emoji_map.data.get(index) else {
+            continue;
+        };
+
+        accuracy_rows += &format!("[{}], [{}/{}], ", data.name, accuracy.exact, accuracy.seen);
+    }
+
+    let header: String = r###"
+        #set page(width: 400pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    // Spoiler free summary to share with other players
+    let share: String = match daily.active {
+        true => daily::share_text(&daily.date_key(), &score).replace('\n', " \\\n"),
+        false => String::new(),
+    };
+
+    let mut leaderboard: String = String::new();
+    if roster.is_multiplayer() {
+        let mut leaderboard_rows: String = String::new();
+        for (rank, player) in roster.leaderboard().into_iter().enumerate() {
+            leaderboard_rows += &format!(
+                "[{}.], [#text(fill: {})[{}]], [{}], ",
+                rank + 1,
+                player.typst_color(),
+                player.name,
+                player.score
+            );
+        }
+
+        leaderboard = format!(
+            "#table(columns: 3, stroke: none, [], [*Player*], [*Score*], {leaderboard_rows})"
+        );
+    }
+
+    let results: String = format!(
+        r###"
+        = Game Over
+        *Score:* {} \
+        *Best streak:* {} \
+        *Seed:* {}
+        {}
+        #table(columns: 2, stroke: none, [*Emoji*], [*Exact*], {})
+        {}
+        "###,
+        score.total, score.best_streak, round_seed.current, leaderboard, accuracy_rows, share
+    );
+
+    if let Ok(results_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + &results)
+    {
+        commands.entity(results_tree.root_entity).insert((
+            Transform::from_xyz(-results_tree.size.x * 0.5, 350.0, 1.0),
+            GameOverUi,
+        ));
+    }
+
+    let mut button_seqs: Vec<Sequence> = Vec::with_capacity(2);
+
+    // The daily challenge can only be played once
+    if daily.active == false {
+        button_seqs.push(menu_ui::create_button::<PlayAgainBtn>(
+            &mut commands,
+            &mut fragments,
+            &mut typst_compiler,
+            DVec2::new(200.0, 70.0),
+            100.0,
+            *palette.get_or_default(&ColorKey::Blue),
+            Vec3::new(-150.0, -350.0, 0.0),
+            OFFSET,
+            "= Again",
+        ));
+    }
+
+    button_seqs.push(menu_ui::create_button::<MainMenuBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 70.0),
+        100.0,
+        *palette.get_or_default(&ColorKey::Orange),
+        Vec3::new(150.0, -350.0, 0.0),
+        OFFSET,
+        "= Menu",
+    ));
+
+    let sequence: Sequence = flow(0.1, &button_seqs).with_ease(ease::cubic::ease_in_out);
+    let sequence_id: Entity = commands.spawn(sequence).id();
+
+    let mut timeline: Timeline = Timeline::new(sequence_id);
+    timeline.time_scale = 1.0;
+    commands.spawn((timeline, SetupTimeline, GameOverUi));
+}
+
+pub fn cleanup(
+    mut commands: Commands,
+    q_game_over_ui: Query<Entity, Or<(With<GameOverUi>, With<PlayAgainBtn>, With<MainMenuBtn>)>>,
+    q_timelines: Query<&Timeline, With<GameOverUi>>,
+) {
+    for timeline in q_timelines.iter() {
+        if let Some(sequence_id) = timeline.sequence_id() {
+            commands.entity(sequence_id).despawn();
+        }
+    }
+
+    for entity in q_game_over_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn button_evt(
+    q_play_again_btn: Query<With<PlayAgainBtn>>,
+    q_main_menu_btn: Query<With<MainMenuBtn>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for clicked in ev_clicked.read() {
+        if q_play_again_btn.contains(clicked.entity) {
+            next_game_state.set(GameState::InGame);
+        } else if q_main_menu_btn.contains(clicked.entity) {
+            next_game_state.set(GameState::Menu);
+        }
+    }
+}
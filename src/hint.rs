@@ -0,0 +1,312 @@
+use bevy::math::DVec2;
+use bevy::{prelude::*, utils::HashSet};
+use bevy_motiongfx::prelude::*;
+use bevy_prng::ChaCha8Rng;
+use motiongfx_typst::TypstCompiler;
+use rand::{seq::SliceRandom, SeedableRng};
+
+use crate::emoji::{self, EmojiMap, RandomNumber};
+use crate::emoji_ui::{self, EmojiGuesses, EmojiMenuBtn, EmojiTile, PlacementIndex, PlaySoundBtn};
+use crate::game::GameState;
+use crate::players::Roster;
+use crate::score::Score;
+use crate::seed;
+use crate::settings::GameSettings;
+use crate::{menu_ui, mouse, SetupTimeline};
+
+/// Points taken off for revealing one emoji of the answer.
+pub const REVEAL_COST: u32 = 60;
+/// Points taken off for removing wrong emojis from the emoji menu.
+pub const REMOVE_COST: u32 = 20;
+/// Points taken off for playing the clip of a single slot.
+pub const SOLO_COST: u32 = 10;
+/// Number of wrong emojis taken off the emoji menu by a single hint.
+const REMOVE_COUNT: usize = 3;
+
+/// Replays and hints left in the current round.
+#[derive(Resource, Default)]
+pub struct HintBudget {
+    pub replays_left: u32,
+    pub hints_left: u32,
+    /// Emoji indices taken off the emoji menu this round, by a hint or for being outside the candidate pool.
+    pub removed: HashSet<usize>,
+}
+
+#[derive(Component, Default)]
+pub struct RevealHintBtn;
+
+#[derive(Component, Default)]
+pub struct RemoveHintBtn;
+
+#[derive(Component, Default)]
+pub struct SoloHintBtn;
+
+/// Remaining budget shown on top of a button.
+#[derive(Component)]
+pub struct BudgetLabel;
+
+pub fn setup_hint_btn(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+) {
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+    let hint_color: Color = *palette.get_or_default(&ColorKey::Blue);
+
+    let reveal_btn_seq: Sequence = menu_ui::create_button::<RevealHintBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 70.0),
+        100.0,
+        hint_color,
+        Vec3::new(-500.0, -100.0, 0.0),
+        Vec3::Y * 100.0,
+        "= 🔍",
+    );
+
+    let remove_btn_seq: Sequence = menu_ui::create_button::<RemoveHintBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 70.0),
+        100.0,
+        hint_color,
+        Vec3::new(-500.0, -200.0, 0.0),
+        Vec3::Y * 100.0,
+        "= ❌",
+    );
+
+    let solo_btn_seq: Sequence = menu_ui::create_button::<SoloHintBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 70.0),
+        100.0,
+        hint_color,
+        Vec3::new(-500.0, -300.0, 0.0),
+        Vec3::Y * 100.0,
+        "= 🎧",
+    );
+
+    let sequence: Sequence = flow(0.1, &[reveal_btn_seq, remove_btn_seq, solo_btn_seq])
+        .with_ease(ease::cubic::ease_in_out);
+    let sequence_id: Entity = commands.spawn(sequence).id();
+
+    let mut timeline: Timeline = Timeline::new(sequence_id);
+    timeline.time_scale = -1.0;
+    commands.spawn((timeline, SetupTimeline, emoji_ui::TileSetupTimeline));
+}
+
+/// Refills the budget at the start of every round.
+pub fn reset_budget(
+    mut hint_budget: ResMut<HintBudget>,
+    settings: Res<GameSettings>,
+    random_number: Res<RandomNumber>,
+    emoji_map: Res<EmojiMap>,
+) {
+    *hint_budget = HintBudget {
+        replays_left: settings.replays_per_round,
+        hints_left: settings.hints_per_round,
+        removed: outside_pool(
+            &random_number.numbers,
+            emoji_map.data.len(),
+            settings.pool_size,
+        ),
+    };
+}
+
+/// Emojis left off the emoji menu so only `pool_size` candidates remain, answer included.
+fn outside_pool(answer: &[usize], emoji_count: usize, pool_size: usize) -> HashSet<usize> {
+    if pool_size == 0 || pool_size >= emoji_count {
+        return HashSet::new();
+    }
+
+    let mut decoys: Vec<usize> = (0..emoji_count)
+        .filter(|index| answer.contains(index) == false)
+        .collect();
+
+    // Seeded by the answer instead of the global rng so the upcoming rounds do not change,
+    // and LAN clients end up with the same pool as the host
+    let pool_seed: u64 = answer.iter().fold(0, |pool_seed: u64, &index| {
+        pool_seed.wrapping_mul(31).wrapping_add(index as u64 + 1)
+    });
+    decoys.shuffle(&mut ChaCha8Rng::from_seed(seed::seed_bytes(pool_seed)));
+
+    decoys
+        .into_iter()
+        .skip(pool_size.saturating_sub(answer.len()))
+        .collect()
+}
+
+/// Hints are only for a single player guessing, they would be shared by everyone guessing at once.
+pub fn hint_button_evt(
+    mut commands: Commands,
+    q_reveal_btn: Query<With<RevealHintBtn>>,
+    q_remove_btn: Query<With<RemoveHintBtn>>,
+    q_solo_btn: Query<With<SoloHintBtn>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut ev_play_sound: EventWriter<emoji::PlaySound>,
+    mut hint_budget: ResMut<HintBudget>,
+    mut guesses: ResMut<EmojiGuesses>,
+    mut score: ResMut<Score>,
+    roster: Res<Roster>,
+    random_number: Res<RandomNumber>,
+    emoji_map: Res<EmojiMap>,
+    placement_index: Res<PlacementIndex>,
+    settings: Res<GameSettings>,
+) {
+    for clicked in ev_clicked.read() {
+        if hint_budget.hints_left == 0 || roster.simultaneous {
+            continue;
+        }
+
+        if q_reveal_btn.contains(clicked.entity) {
+            let Some((slot, answer)) =
+                reveal_target(&guesses.numbers, &random_number.numbers, settings.ordered)
+            else {
+                continue;
+            };
+
+            score.spend(REVEAL_COST);
+
+            // The emoji can only be placed once
+            if let Some(placed) = guesses.numbers.iter().position(|&n| n == answer as i32) {
+                emoji_ui::remove_guess(&mut commands, &mut guesses, placed);
+            }
+            emoji_ui::place_guess(&mut commands, &mut guesses, &emoji_map, slot, answer);
+        } else if q_remove_btn.contains(clicked.entity) {
+            let mut candidates: Vec<usize> = (0..emoji_map.data.len())
+                .filter(|index| {
+                    random_number.numbers.contains(index) == false
+                        && hint_budget.removed.contains(index) == false
+                        && emoji_ui::array_contain_number(&guesses.numbers, *index as i32) == false
+                })
+                .collect();
+
+            if candidates.is_empty() {
+                continue;
+            }
+            score.spend(REMOVE_COST);
+
+            // Not drawn from the global rng so hints do not change the upcoming rounds
+            candidates.shuffle(&mut rand::thread_rng());
+            hint_budget
+                .removed
+                .extend(candidates.into_iter().take(REMOVE_COUNT));
+        } else if q_solo_btn.contains(clicked.entity) {
+            score.spend(SOLO_COST);
+
+            ev_play_sound.send(emoji::PlaySound {
+                slot: Some(placement_index.0),
+            });
+        } else {
+            continue;
+        }
+
+        hint_budget.hints_left -= 1;
+    }
+}
+
+/// Slot and emoji to fill in with the reveal hint.
+fn reveal_target(guesses: &[i32], answer: &[usize], ordered: bool) -> Option<(usize, usize)> {
+    if ordered {
+        // First slot that does not hold the right emoji yet
+        return (0..answer.len())
+            .find(|&slot| guesses[slot] != answer[slot] as i32)
+            .map(|slot| (slot, answer[slot]));
+    }
+
+    // Any missing emoji, placed in the first slot without a correct guess
+    let answer_index: usize = *answer
+        .iter()
+        .find(|&&index| guesses.contains(&(index as i32)) == false)?;
+    let slot: usize = guesses
+        .iter()
+        .position(|&guess| guess < 0 || answer.contains(&(guess as usize)) == false)?;
+
+    Some((slot, answer_index))
+}
+
+/// Hides emoji menu tiles that were removed by a hint, and shows them again once the round is over.
+pub fn hide_removed_tiles(
+    mut q_emoji_tiles: Query<(&EmojiTile, &mut Visibility), With<EmojiMenuBtn>>,
+    hint_budget: Res<HintBudget>,
+    emoji_map: Res<EmojiMap>,
+) {
+    for (emoji_tile, mut visibility) in q_emoji_tiles.iter_mut() {
+        // The last page might not be full
+        if emoji_tile.index >= emoji_map.data.len() {
+            continue;
+        }
+
+        *visibility = match hint_budget.removed.contains(&emoji_tile.index) {
+            true => Visibility::Hidden,
+            false => Visibility::Inherited,
+        };
+    }
+}
+
+/// Shows the remaining replays and hints on their buttons.
+pub fn spawn_budget_labels(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_budget_labels: Query<Entity, With<BudgetLabel>>,
+    q_play_sound_btn: Query<Entity, With<PlaySoundBtn>>,
+    q_hint_btns: Query<Entity, Or<(With<RevealHintBtn>, With<RemoveHintBtn>, With<SoloHintBtn>)>>,
+    hint_budget: Res<HintBudget>,
+    game_state: Res<State<GameState>>,
+) {
+    for entity in q_budget_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if matches!(
+        game_state.get(),
+        GameState::InGame | GameState::RoundReveal | GameState::Paused
+    ) == false
+    {
+        return;
+    }
+
+    let header: String = r###"
+        #set page(width: auto, height: auto, margin: 4pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    let buttons = q_play_sound_btn
+        .iter()
+        .map(|entity| (entity, hint_budget.replays_left))
+        .chain(
+            q_hint_btns
+                .iter()
+                .map(|entity| (entity, hint_budget.hints_left)),
+        );
+
+    for (button, budget) in buttons {
+        let Ok(label_tree) = typst_compiler.compile_flatten(
+            &mut commands,
+            &mut fragments,
+            header.clone() + &format!("*{budget}*"),
+        ) else {
+            continue;
+        };
+
+        // Top right corner of the button
+        commands
+            .entity(label_tree.root_entity)
+            .insert((Transform::from_xyz(70.0, 30.0, 2.0), BudgetLabel))
+            .set_parent(button);
+    }
+}
+
+pub fn despawn_budget_labels(
+    mut commands: Commands,
+    q_budget_labels: Query<Entity, With<BudgetLabel>>,
+) {
+    for entity in q_budget_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,868 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::emoji::{self, EmojiMap, RandomNumber};
+use crate::emoji_ui::{self, EmojiGuesses};
+use crate::game::GameState;
+use crate::party::{LockIn, PartyRound};
+use crate::players::{Player, Roster, MAX_NAME_LEN, MAX_PLAYERS, PLAYER_COLORS};
+use crate::score::Score;
+use crate::settings::GameSettings;
+
+/// Port the host listens on when none is given.
+pub const DEFAULT_PORT: u16 = 7878;
+/// Seconds between two attempts of a client to reach the host.
+const RECONNECT_INTERVAL: f32 = 2.0;
+/// Seconds a round waits for a disconnected client before locking in for them.
+const RECONNECT_GRACE: f32 = 15.0;
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Seconds a new connection has to send [`NetMessage::Join`] before it is dropped.
+const JOIN_TIMEOUT: f32 = 5.0;
+/// Most bytes kept of a message that has not ended yet, or of messages waiting to be sent.
+const MAX_BUFFERED: usize = 64 * 1024;
+/// Letters used in join codes, without the ones that are easily mixed up.
+const CODE_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const CODE_LEN: usize = 4;
+
+/// Messages sent between the host and its clients, one RON value per line over TCP.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum NetMessage {
+    /// Client asking to join, `token` lets a client take its seat back after reconnecting.
+    Join {
+        code: String,
+        name: String,
+        token: u64,
+    },
+    /// Guesses of a client that locked in.
+    Guess { round: usize, numbers: Vec<i32> },
+    /// Host turning a client away.
+    Rejected { reason: String },
+    /// Names, colors and scores of every player, `you` being the receiving client.
+    Roster {
+        players: Vec<(String, usize, u32)>,
+        you: usize,
+    },
+    /// Answer and settings of a new round.
+    RoundStart {
+        round: usize,
+        settings: GameSettings,
+        numbers: Vec<usize>,
+    },
+    /// Screen the host moved to.
+    State(GameState),
+}
+
+struct Connection {
+    stream: TcpStream,
+    received: Vec<u8>,
+    /// Bytes the socket did not take yet, sent on later frames.
+    outgoing: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            stream,
+            received: Vec::new(),
+            outgoing: Vec::new(),
+        })
+    }
+
+    fn send(&mut self, message: &NetMessage) -> io::Result<()> {
+        let mut line: String = ron::to_string(message)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        line.push('\n');
+        self.outgoing.extend_from_slice(line.as_bytes());
+        self.flush()
+    }
+
+    /// Writes as much of the queued bytes as the socket takes without blocking.
+    fn flush(&mut self) -> io::Result<()> {
+        while self.outgoing.is_empty() == false {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(len) => {
+                    self.outgoing.drain(..len);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        // The other side stopped reading
+        if self.outgoing.len() > MAX_BUFFERED {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "too many unsent messages",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sends what is still queued and returns the messages received since the last call,
+    /// an error once the connection is closed.
+    fn receive(&mut self) -> io::Result<Vec<NetMessage>> {
+        self.flush()?;
+
+        let mut buffer: [u8; 1024] = [0; 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(len) => self.received.extend_from_slice(&buffer[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut messages: Vec<NetMessage> = Vec::new();
+        while let Some(end) = self.received.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.received.drain(..=end).collect();
+            match std::str::from_utf8(&line)
+                .map_err(|err| err.to_string())
+                .and_then(|line| ron::from_str(line.trim()).map_err(|err| err.to_string()))
+            {
+                Ok(message) => messages.push(message),
+                Err(err) => warn!("Ignoring malformed message: {err}"),
+            }
+        }
+
+        if self.received.len() > MAX_BUFFERED {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message too long",
+            ));
+        }
+
+        Ok(messages)
+    }
+}
+
+/// Client that joined the lobby, keeping its seat while disconnected.
+struct RemoteClient {
+    token: u64,
+    connection: Option<Connection>,
+    /// Time the connection was found to be lost.
+    lost_at: Option<f32>,
+}
+
+pub struct LanHost {
+    listener: TcpListener,
+    pub code: String,
+    pub port: u16,
+    /// Connections that have not sent [`NetMessage::Join`] yet, with the time they were accepted.
+    pending: Vec<(Connection, f32)>,
+    clients: Vec<RemoteClient>,
+}
+
+impl LanHost {
+    fn broadcast_roster(&mut self, roster: &Roster) {
+        let players: Vec<(String, usize, u32)> = roster
+            .players
+            .iter()
+            .map(|player| (player.name.clone(), player.color, player.score))
+            .collect();
+
+        for client in self.clients.iter_mut() {
+            let Some(you) = roster.seat(client.token) else {
+                continue;
+            };
+
+            send_or_drop(
+                &mut client.connection,
+                &NetMessage::Roster {
+                    players: players.clone(),
+                    you,
+                },
+            );
+        }
+    }
+
+    fn broadcast(&mut self, message: &NetMessage) {
+        for client in self.clients.iter_mut() {
+            send_or_drop(&mut client.connection, message);
+        }
+    }
+}
+
+pub struct LanClient {
+    pub address: SocketAddr,
+    pub code: String,
+    token: u64,
+    /// Name sent to the host, taken from the first local player.
+    name: Option<String>,
+    connection: Option<Connection>,
+    /// Connection attempt on its own thread, so the game keeps running while the host answers.
+    connecting: Option<JoinHandle<io::Result<TcpStream>>>,
+    /// Time of the next connection attempt.
+    next_attempt: f32,
+    pub rejected: Option<String>,
+    /// Round the host is playing and its answer.
+    round: Option<usize>,
+    numbers: Vec<usize>,
+}
+
+/// Role of this instance in a game over the local network.
+#[derive(Resource, Default)]
+pub enum Lan {
+    #[default]
+    Offline,
+    Host(LanHost),
+    Client(LanClient),
+}
+
+impl Lan {
+    /// Reads `--host [port]` or `--join <address[:port]> <code>` from the command line.
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+
+        if let Some(index) = args.iter().position(|arg| arg == "--host") {
+            let port: u16 = args
+                .get(index + 1)
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(DEFAULT_PORT);
+            return Self::host(port);
+        }
+
+        if let Some(index) = args.iter().position(|arg| arg == "--join") {
+            let (Some(address), Some(code)) = (args.get(index + 1), args.get(index + 2)) else {
+                warn!("Usage: --join <address[:port]> <code>");
+                return Self::Offline;
+            };
+            return Self::join(address, code);
+        }
+
+        Self::Offline
+    }
+
+    pub fn host(port: u16) -> Self {
+        let listener: TcpListener = match TcpListener::bind(("0.0.0.0", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Unable to host on port {port}: {err}");
+                return Self::Offline;
+            }
+        };
+
+        // Port 0 leaves the choice to the system
+        let port: u16 = listener
+            .local_addr()
+            .map(|address| address.port())
+            .unwrap_or(port);
+
+        let mut rng = rand::thread_rng();
+        let code: String = (0..CODE_LEN)
+            .map(|_| CODE_LETTERS[rng.gen_range(0..CODE_LETTERS.len())] as char)
+            .collect();
+        info!("Hosting lobby {code} on port {port}");
+
+        Self::Host(LanHost {
+            listener,
+            code,
+            port,
+            pending: Vec::new(),
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn join(address: &str, code: &str) -> Self {
+        let address: String = match address.contains(':') {
+            true => address.to_string(),
+            false => format!("{address}:{DEFAULT_PORT}"),
+        };
+
+        let Some(address) = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+        else {
+            error!("Unable to resolve host address \"{address}\"");
+            return Self::Offline;
+        };
+
+        Self::Client(LanClient {
+            address,
+            code: code.to_uppercase(),
+            token: rand::random(),
+            name: None,
+            connection: None,
+            connecting: None,
+            next_attempt: 0.0,
+            rejected: None,
+            round: None,
+            numbers: Vec::new(),
+        })
+    }
+
+    pub fn is_client(&self) -> bool {
+        matches!(self, Lan::Client(_))
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(self, Lan::Offline)
+    }
+
+    /// Answer of the current round decided by the host, only known to clients.
+    pub fn round_numbers(&self) -> Option<&[usize]> {
+        match self {
+            Lan::Client(client) if client.round.is_some() => Some(&client.numbers),
+            _ => None,
+        }
+    }
+}
+
+/// Connection state shown on the main menu.
+#[derive(Resource, Default, PartialEq)]
+pub struct LanStatus(pub String);
+
+/// Marker for the connection state on the main menu.
+#[derive(Component)]
+pub struct LanLabel;
+
+/// Sends a message, forgetting the connection if it was lost.
+fn send_or_drop(connection: &mut Option<Connection>, message: &NetMessage) {
+    if let Some(open) = connection.as_mut() {
+        if let Err(err) = open.send(message) {
+            warn!("Lost connection: {err}");
+            *connection = None;
+        }
+    }
+}
+
+/// Name a client asked for, keeping only what can be typed on the main menu.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphabetic() || *c == ' ')
+        .take(MAX_NAME_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Accepts new clients and applies the guesses of the joined ones.
+pub fn host_update(
+    mut lan: ResMut<Lan>,
+    mut lan_status: ResMut<LanStatus>,
+    mut roster: ResMut<Roster>,
+    mut guesses: ResMut<EmojiGuesses>,
+    mut ev_lock_in: EventWriter<LockIn>,
+    party_round: Res<PartyRound>,
+    game_state: Res<State<GameState>>,
+    score: Res<Score>,
+    random_number: Res<RandomNumber>,
+    settings: Res<GameSettings>,
+    emoji_map: Res<EmojiMap>,
+    time: Res<Time>,
+) {
+    let Lan::Host(host) = lan.as_mut() else {
+        return;
+    };
+
+    loop {
+        match host.listener.accept() {
+            Ok((stream, address)) => match Connection::new(stream) {
+                Ok(connection) => {
+                    info!("Connection from {address}");
+                    host.pending.push((connection, time.elapsed_seconds()));
+                }
+                Err(err) => warn!("Unable to set up connection from {address}: {err}"),
+            },
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => {
+                warn!("Unable to accept connection: {err}");
+                break;
+            }
+        }
+    }
+
+    let board_visible: bool = matches!(
+        game_state.get(),
+        GameState::InGame | GameState::RoundReveal | GameState::Paused
+    );
+
+    // Seat pending connections
+    for (mut connection, accepted_at) in std::mem::take(&mut host.pending) {
+        let messages: Vec<NetMessage> = match connection.receive() {
+            Ok(messages) => messages,
+            Err(_) => continue,
+        };
+
+        let Some((code, name, token)) = messages.into_iter().find_map(|message| match message {
+            NetMessage::Join { code, name, token } => Some((code, name, token)),
+            _ => None,
+        }) else {
+            if time.elapsed_seconds() - accepted_at < JOIN_TIMEOUT {
+                host.pending.push((connection, accepted_at));
+            } else {
+                info!("Dropping a connection that never joined");
+            }
+            continue;
+        };
+
+        let reason: Option<&str> = if code.to_uppercase() != host.code {
+            Some("wrong join code")
+        } else if roster.seat(token).is_none() && roster.players.len() >= MAX_PLAYERS {
+            Some("lobby is full")
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            let _ = connection.send(&NetMessage::Rejected {
+                reason: reason.to_string(),
+            });
+            continue;
+        }
+
+        let index: usize = roster.players.len();
+        let name: String = match sanitize_name(&name) {
+            name if name.is_empty() => Player::new(index).name,
+            name => name,
+        };
+
+        match roster.seat(token) {
+            Some(_) => info!("{name} reconnected"),
+            None => {
+                info!("{name} joined the lobby");
+                roster.players.push(Player {
+                    name,
+                    remote: Some(token),
+                    ..Player::new(index)
+                });
+                roster.simultaneous = true;
+            }
+        }
+        // Also sends the roster to the new client
+        roster.set_changed();
+
+        // Catch up with a round in progress
+        if board_visible {
+            let _ = connection.send(&NetMessage::RoundStart {
+                round: score.rounds.len(),
+                settings: settings.clone(),
+                numbers: random_number.numbers.clone(),
+            });
+        }
+        if *game_state.get() == GameState::RoundReveal {
+            let _ = connection.send(&NetMessage::State(GameState::RoundReveal));
+        }
+
+        host.clients.retain(|client| client.token != token);
+        host.clients.push(RemoteClient {
+            token,
+            connection: Some(connection),
+            lost_at: None,
+        });
+    }
+
+    for client in host.clients.iter_mut() {
+        let Some(player) = roster.seat(client.token) else {
+            continue;
+        };
+
+        if let Some(connection) = client.connection.as_mut() {
+            match connection.receive() {
+                Ok(messages) => {
+                    for message in messages {
+                        let NetMessage::Guess { round, numbers } = message else {
+                            continue;
+                        };
+
+                        // Guesses from a round that is already over, or after locking in
+                        if round != score.rounds.len() || party_round.lock_order.contains(&player) {
+                            continue;
+                        }
+
+                        let Some(rival) = guesses.rivals.iter_mut().find(|r| r.player == player)
+                        else {
+                            continue;
+                        };
+                        if numbers.len() != rival.numbers.len()
+                            || is_valid_guess(&numbers, emoji_map.names.len()) == false
+                        {
+                            warn!(
+                                "Ignored invalid guesses from {}",
+                                roster.players[player].name
+                            );
+                            continue;
+                        }
+
+                        rival.numbers = numbers;
+                        ev_lock_in.send(LockIn { player });
+                        // Locked in, the rest would only be guesses sent again
+                        break;
+                    }
+                }
+                Err(err) => {
+                    warn!("Lost connection to {}: {err}", roster.players[player].name);
+                    client.connection = None;
+                }
+            }
+        }
+
+        if client.connection.is_none() && client.lost_at.is_none() {
+            client.lost_at = Some(time.elapsed_seconds());
+        }
+
+        // Nobody waits for a player that is gone for good
+        if client
+            .lost_at
+            .is_some_and(|lost_at| time.elapsed_seconds() - lost_at >= RECONNECT_GRACE)
+            && *game_state.get() == GameState::InGame
+            && player < party_round.devices.len()
+            && party_round.lock_order.contains(&player) == false
+        {
+            ev_lock_in.send(LockIn { player });
+        }
+    }
+
+    // Players gone for good give their seat up between games
+    if *game_state.get() == GameState::Menu {
+        let now: f32 = time.elapsed_seconds();
+        let gone: Vec<u64> = host
+            .clients
+            .iter()
+            .filter(|client| {
+                client
+                    .lost_at
+                    .is_some_and(|lost_at| now - lost_at >= RECONNECT_GRACE)
+            })
+            .map(|client| client.token)
+            .collect();
+
+        if gone.is_empty() == false {
+            host.clients
+                .retain(|client| gone.contains(&client.token) == false);
+            roster.players.retain(|player| {
+                matches!(player.remote, Some(token) if gone.contains(&token)) == false
+            });
+
+            let last: usize = roster.players.len().saturating_sub(1);
+            roster.current = roster.current.min(last);
+            roster.selected = roster.selected.min(last);
+            info!("Dropped {} disconnected players from the lobby", gone.len());
+        }
+    }
+
+    let connected: usize = host
+        .clients
+        .iter()
+        .filter(|client| client.connection.is_some())
+        .count();
+    lan_status.set_if_neq(LanStatus(format!(
+        "LAN code *{}* on port {} \\ {connected} connected",
+        host.code, host.port
+    )));
+}
+
+pub fn host_send_roster(mut lan: ResMut<Lan>, roster: Res<Roster>) {
+    if let Lan::Host(host) = lan.as_mut() {
+        host.broadcast_roster(&roster);
+    }
+}
+
+/// Sends the answer of every new round to the clients.
+pub fn host_send_round(
+    mut lan: ResMut<Lan>,
+    random_number: Res<RandomNumber>,
+    settings: Res<GameSettings>,
+    score: Res<Score>,
+) {
+    if let Lan::Host(host) = lan.as_mut() {
+        host.broadcast(&NetMessage::RoundStart {
+            round: score.rounds.len(),
+            settings: settings.clone(),
+            numbers: random_number.numbers.clone(),
+        });
+    }
+}
+
+/// Moves the clients along with the reveal, the end of the game and the main menu.
+pub fn host_send_state(mut lan: ResMut<Lan>, game_state: Res<State<GameState>>) {
+    if let Lan::Host(host) = lan.as_mut() {
+        if matches!(
+            game_state.get(),
+            GameState::RoundReveal | GameState::GameOver | GameState::Menu
+        ) {
+            host.broadcast(&NetMessage::State(*game_state.get()));
+        }
+    }
+}
+
+/// Connects to the host, reconnecting when the connection is lost, and follows its rounds.
+pub fn client_update(
+    mut commands: Commands,
+    mut lan: ResMut<Lan>,
+    mut lan_status: ResMut<LanStatus>,
+    mut roster: ResMut<Roster>,
+    mut settings: ResMut<GameSettings>,
+    mut guesses: ResMut<EmojiGuesses>,
+    mut ev_gen_rand_num: EventWriter<emoji::GenerateRandomNumber>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+    emoji_map: Res<EmojiMap>,
+    time: Res<Time>,
+) {
+    let Lan::Client(client) = lan.as_mut() else {
+        return;
+    };
+
+    if let Some(reason) = client.rejected.as_ref() {
+        lan_status.set_if_neq(LanStatus(format!("Rejected by the host: {reason}")));
+        return;
+    }
+
+    if client.connection.is_none() {
+        if client.connecting.is_none() {
+            if time.elapsed_seconds() < client.next_attempt {
+                return;
+            }
+            client.next_attempt = time.elapsed_seconds() + RECONNECT_INTERVAL;
+
+            let address: SocketAddr = client.address;
+            client.connecting = Some(thread::spawn(move || {
+                TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+            }));
+        }
+
+        // Still waiting for the host to answer
+        if client
+            .connecting
+            .as_ref()
+            .is_some_and(|connecting| connecting.is_finished() == false)
+        {
+            return;
+        }
+        let Some(connecting) = client.connecting.take() else {
+            return;
+        };
+
+        let name: String = client
+            .name
+            .get_or_insert_with(|| roster.players[0].name.clone())
+            .clone();
+
+        let connection = connecting
+            .join()
+            .unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "connect thread panicked",
+                ))
+            })
+            .and_then(Connection::new)
+            .and_then(|mut connection| {
+                connection
+                    .send(&NetMessage::Join {
+                        code: client.code.clone(),
+                        name,
+                        token: client.token,
+                    })
+                    .map(|_| connection)
+            });
+
+        match connection {
+            Ok(connection) => {
+                info!("Connected to {}", client.address);
+                client.connection = Some(connection);
+            }
+            Err(err) => {
+                lan_status.set_if_neq(LanStatus(format!("Connecting to {}...", client.address)));
+                debug!("Unable to reach {}: {err}", client.address);
+                return;
+            }
+        }
+    }
+
+    let Some(connection) = client.connection.as_mut() else {
+        return;
+    };
+
+    let messages: Vec<NetMessage> = match connection.receive() {
+        Ok(messages) => messages,
+        Err(err) => {
+            warn!("Lost connection to the host: {err}");
+            client.connection = None;
+            lan_status.set_if_neq(LanStatus("Lost the host, reconnecting...".into()));
+            return;
+        }
+    };
+
+    lan_status.set_if_neq(LanStatus(format!(
+        "Joined lobby *{}* \\ waiting for the host",
+        client.code
+    )));
+
+    for message in messages {
+        match message {
+            NetMessage::Rejected { reason } => {
+                warn!("Rejected by the host: {reason}");
+                client.rejected = Some(reason);
+                client.connection = None;
+                return;
+            }
+            NetMessage::Roster { players, you } => {
+                if you >= players.len() || players.len() > MAX_PLAYERS {
+                    warn!(
+                        "Ignored a roster of {} players from the host",
+                        players.len()
+                    );
+                    continue;
+                }
+
+                roster.players = players
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (name, color, score))| Player {
+                        name,
+                        color: color % PLAYER_COLORS.len(),
+                        score,
+                        // Only this client's own player is kept between sessions
+                        remote: (index != you).then_some(0),
+                    })
+                    .collect();
+                roster.current = you;
+                roster.selected = you;
+                roster.simultaneous = true;
+            }
+            NetMessage::RoundStart {
+                round,
+                settings: mut host_settings,
+                numbers,
+            } => {
+                host_settings.clamp_to_limits();
+                if numbers.len() != host_settings.answer_len()
+                    || numbers.iter().any(|&index| index >= emoji_map.names.len())
+                {
+                    warn!(
+                        "Ignored round {round} from the host, its answer does not fit the emojis"
+                    );
+                    continue;
+                }
+
+                let board_visible: bool = matches!(
+                    game_state.get(),
+                    GameState::InGame | GameState::RoundReveal | GameState::Paused
+                );
+
+                // Already playing this round before reconnecting
+                let same_round: bool = client.round == Some(round) && board_visible;
+                client.round = Some(round);
+                client.numbers = numbers;
+                if same_round {
+                    continue;
+                }
+
+                if *settings != host_settings {
+                    *settings = host_settings;
+                }
+
+                // Starting from the main menu generates the first round on its own
+                if board_visible {
+                    emoji_ui::clear_guesses(&mut commands, &mut guesses);
+                    ev_gen_rand_num.send(emoji::GenerateRandomNumber);
+                }
+                next_game_state.set(GameState::InGame);
+            }
+            NetMessage::State(state) => match state {
+                GameState::RoundReveal if *game_state.get() == GameState::InGame => {
+                    next_game_state.set(GameState::RoundReveal)
+                }
+                GameState::GameOver | GameState::Menu if *game_state.get() != state => {
+                    client.round = None;
+                    next_game_state.set(state);
+                }
+                _ => {}
+            },
+            NetMessage::Join { .. } | NetMessage::Guess { .. } => {}
+        }
+    }
+}
+
+/// Whether guesses from a client are distinct emoji indices, -1 for an empty slot.
+fn is_valid_guess(numbers: &[i32], emoji_count: usize) -> bool {
+    numbers.iter().enumerate().all(|(slot, &number)| {
+        number == -1
+            || (number >= 0
+                && (number as usize) < emoji_count
+                && numbers[..slot].contains(&number) == false)
+    })
+}
+
+/// Sends the guesses to the host once they are locked in.
+pub fn client_send_guess(
+    mut lan: ResMut<Lan>,
+    mut ev_lock_in: EventReader<LockIn>,
+    guesses: Res<EmojiGuesses>,
+) {
+    let Lan::Client(client) = lan.as_mut() else {
+        ev_lock_in.clear();
+        return;
+    };
+
+    for lock_in in ev_lock_in.read() {
+        let Some(round) = client.round else {
+            continue;
+        };
+
+        if lock_in.player == 0 {
+            send_or_drop(
+                &mut client.connection,
+                &NetMessage::Guess {
+                    round,
+                    numbers: guesses.numbers.clone(),
+                },
+            );
+        }
+    }
+}
+
+pub fn spawn_lan_label(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_lan_labels: Query<Entity, With<LanLabel>>,
+    lan_status: Res<LanStatus>,
+) {
+    for entity in q_lan_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if lan_status.0.is_empty() {
+        return;
+    }
+
+    let header: String = r###"
+        #set page(width: 300pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    let Ok(label_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + &lan_status.0)
+    else {
+        return;
+    };
+
+    commands
+        .entity(label_tree.root_entity)
+        .insert((Transform::from_xyz(150.0, -150.0, 1.0), LanLabel));
+}
+
+pub fn despawn_lan_label(mut commands: Commands, q_lan_labels: Query<Entity, With<LanLabel>>) {
+    for entity in q_lan_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
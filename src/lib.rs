@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+
+pub mod audience;
+pub mod daily;
+pub mod emoji;
+pub mod emoji_ui;
+pub mod game;
+pub mod game_over_ui;
+pub mod hint;
+pub mod lan;
+pub mod loading_ui;
+pub mod menu_ui;
+pub mod mouse;
+pub mod party;
+pub mod players;
+pub mod round_timer;
+pub mod save;
+pub mod score;
+pub mod seed;
+pub mod server;
+pub mod settings;
+
+#[derive(Component)]
+pub struct SetupTimeline;
+
+pub fn setup_animation_update(
+    mut q_timelines: Query<&mut Timeline, With<SetupTimeline>>,
+    q_sequences: Query<&Sequence>,
+    time: Res<Time>,
+) {
+    for mut timeline in q_timelines.iter_mut() {
+        let Ok(sequence) = q_sequences.get(timeline.sequence_id().unwrap()) else {
+            continue;
+        };
+
+        // stops updating when timeline reaches the end
+        if (timeline.time_scale > 0.0 && timeline.target_time >= sequence.duration())
+            || (timeline.time_scale < 0.0 && timeline.target_time <= 0.0)
+        {
+            continue;
+        }
+
+        timeline.target_time += timeline.time_scale * time.delta_seconds();
+    }
+}
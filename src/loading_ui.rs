@@ -0,0 +1,102 @@
+use bevy::math::DVec4;
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+
+use crate::emoji::LoadingProgress;
+
+#[derive(Component)]
+pub struct LoadingUi;
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+const BAR_SIZE: Vec2 = Vec2::new(400.0, 20.0);
+
+pub fn setup(mut commands: Commands, mut fragments: ResMut<Assets<VelloFragment>>) {
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+
+    let background_color: Color = *palette.get_or_default(&ColorKey::Base2);
+    let stroke_color: Color = *palette.get_or_default(&ColorKey::Base8);
+    let bar_color: Color = *palette.get_or_default(&ColorKey::Blue);
+
+    commands.spawn((
+        VelloRectBundle {
+            rect: VelloRect::anchor_center(BAR_SIZE.as_dvec2(), DVec4::splat(0.0)),
+            fill: FillStyle::from_brush(background_color),
+            stroke: StrokeStyle::from_brush(stroke_color).with_style(2.0),
+            fragment_bundle: VelloFragmentBundle {
+                fragment: fragments.add(VelloFragment::default()),
+                transform: TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, -1.0)),
+                ..default()
+            },
+        },
+        LoadingUi,
+    ));
+
+    commands.spawn((
+        VelloRectBundle {
+            rect: VelloRect::anchor_center(BAR_SIZE.as_dvec2(), DVec4::splat(0.0)),
+            fill: FillStyle::from_brush(bar_color),
+            stroke: StrokeStyle::from_brush(Color::NONE).with_style(0.0),
+            fragment_bundle: VelloFragmentBundle {
+                fragment: fragments.add(VelloFragment::default()),
+                transform: TransformBundle::from_transform(
+                    Transform::from_xyz(0.0, 0.0, 0.0).with_scale(Vec3::new(0.0, 1.0, 1.0)),
+                ),
+                ..default()
+            },
+        },
+        LoadingUi,
+        LoadingBar,
+    ));
+}
+
+pub fn update_bar(
+    mut q_bars: Query<&mut Transform, With<LoadingBar>>,
+    progress: Res<LoadingProgress>,
+) {
+    let ratio: f32 = progress.ratio();
+
+    for mut transform in q_bars.iter_mut() {
+        // Grow the bar from its left edge
+        transform.scale.x = ratio;
+        transform.translation.x = -BAR_SIZE.x * 0.5 * (1.0 - ratio);
+    }
+}
+
+/// Shows why loading is stuck below the bar.
+pub fn show_error(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    progress: Res<LoadingProgress>,
+) {
+    let Some(error) = progress.error.as_ref() else {
+        return;
+    };
+
+    let header: String = r###"
+        #set page(width: 600pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FF6188"))
+        #set align(center)
+    "###
+    .into();
+
+    let Ok(label_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + error)
+    else {
+        return;
+    };
+
+    commands.entity(label_tree.root_entity).insert((
+        Transform::from_xyz(-label_tree.size.x * 0.5, -30.0, 1.0),
+        LoadingUi,
+    ));
+}
+
+pub fn cleanup(mut commands: Commands, q_loading_ui: Query<Entity, With<LoadingUi>>) {
+    for entity in q_loading_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
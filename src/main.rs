@@ -1,13 +1,14 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, time::common_conditions::on_timer, window::WindowCloseRequested};
 use bevy_motiongfx::prelude::*;
+use bevy_prng::ChaCha8Rng;
+use bevy_rand::prelude::EntropyPlugin;
 use bevy_rapier2d::prelude::*;
 use motiongfx_typst::TypstCompilerPlugin;
 
-mod emoji;
-mod emoji_ui;
-mod game;
-mod menu_ui;
-mod mouse;
+use ggj2024::{
+    audience, daily, emoji, emoji_ui, game, game_over_ui, hint, lan, loading_ui, menu_ui, mouse,
+    party, players, round_timer, save, score, seed, settings, setup_animation_update,
+};
 
 fn main() {
     App::new()
@@ -21,81 +22,343 @@ fn main() {
         ))
         .add_plugins(bevy_vello::VelloPlugin)
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
+        .add_plugins(EntropyPlugin::<ChaCha8Rng>::default())
         // .add_plugins(RapierDebugRenderPlugin::default())
+        // Assets
+        .init_asset::<emoji::EmojiPack>()
+        .init_asset_loader::<emoji::EmojiPackLoader>()
+        // States
+        .add_state::<game::GameState>()
         // Resources
         .insert_resource(mouse::PreviousClicked::default())
         .insert_resource(emoji::EmojiMap::default())
+        .insert_resource(emoji::LoadingProgress::default())
         .insert_resource(emoji::RandomNumber::default())
-        .insert_resource(game::GameStateRes::default())
+        .insert_resource(emoji::AudioSchedule::default())
+        .insert_resource(emoji::ClipDurations::default())
+        .insert_resource(game::PausedFrom::default())
+        .insert_resource(settings::GameSettings::default())
+        .insert_resource(score::Score::default())
+        .insert_resource(seed::RoundSeed::from_args())
+        .insert_resource(daily::DailyChallenge::default())
         .insert_resource(emoji_ui::PlacementIndex(0))
+        .insert_resource(emoji_ui::EmojiPage::default())
         .insert_resource(emoji_ui::EmojiGuesses::default())
+        .insert_resource(hint::HintBudget::default())
+        .insert_resource(players::Roster::default())
+        .insert_resource(party::PartyRound::default())
+        .insert_resource(round_timer::RoundTimer::default())
+        .insert_resource(lan::Lan::from_args())
+        .insert_resource(lan::LanStatus::default())
+        .insert_resource(audience::AudienceServer::from_args())
+        .insert_resource(audience::AudienceVotes::default())
         .add_event::<mouse::Clicked>()
+        .add_event::<mouse::RightClicked>()
+        .add_event::<mouse::Released>()
         .add_event::<emoji::PlaySound>()
         .add_event::<emoji::GenerateRandomNumber>()
+        .add_event::<score::RoundScored>()
+        .add_event::<party::LockIn>()
         // .add_systems(Startup, (setup, board::setup))
         // Systems
         .add_systems(PreStartup, emoji::load_emoji_data)
-        .add_systems(Startup, setup)
+        .add_systems(Startup, (setup, save::load_save))
         .add_systems(Startup, menu_ui::menu_button)
+        .add_systems(Startup, (emoji_ui::setup_action_btn, hint::setup_hint_btn))
+        // Loading
+        .add_systems(OnEnter(game::GameState::Loading), loading_ui::setup)
         .add_systems(
-            Startup,
+            OnExit(game::GameState::Loading),
+            (emoji_ui::setup_menu, loading_ui::cleanup),
+        )
+        .add_systems(
+            Update,
+            (
+                emoji::sync_emoji_map,
+                emoji::report_emoji_errors,
+                (
+                    emoji::track_loading,
+                    loading_ui::update_bar,
+                    loading_ui::show_error.run_if(resource_changed::<emoji::LoadingProgress>()),
+                )
+                    .chain()
+                    .run_if(in_state(game::GameState::Loading)),
+            )
+                .chain(),
+        )
+        // Menu
+        .add_systems(
+            OnEnter(game::GameState::Menu),
+            (
+                game::show_menu,
+                emoji_ui::rewind_reveal,
+                seed::spawn_seed_label,
+                menu_ui::spawn_order_label,
+                players::spawn_roster_label,
+                daily::end_challenge,
+                emoji::stop_audio,
+                hint::despawn_budget_labels,
+                party::despawn_cursors,
+                lan::spawn_lan_label,
+                round_timer::despawn_timer_bar,
+            ),
+        )
+        .add_systems(
+            OnExit(game::GameState::Menu),
             (
-                emoji_ui::setup,
-                emoji_ui::setup_menu,
-                emoji_ui::setup_action_btn,
+                lan::despawn_lan_label,
+                seed::despawn_seed_label,
+                menu_ui::despawn_order_label,
+                players::despawn_roster_label,
+                daily::despawn_daily_label,
+            ),
+        )
+        .add_systems(
+            OnTransition {
+                from: game::GameState::Menu,
+                to: game::GameState::InGame,
+            },
+            (
+                game::show_board,
+                game::new_game,
+                score::reset_score,
+                players::reset_scores,
+                seed::reseed,
             ),
         )
+        .add_systems(
+            Update,
+            (
+                menu_ui::start_button_evt.before(emoji_ui::setup),
+                // Settings have to change before the placement row is rebuilt
+                daily::daily_button_evt.before(emoji_ui::setup),
+                menu_ui::quit_button_evt,
+                (
+                    menu_ui::difficulty_button_evt,
+                    menu_ui::order_button_evt,
+                    menu_ui::spawn_order_label.run_if(resource_changed::<settings::GameSettings>()),
+                )
+                    .chain(),
+                (
+                    seed::seed_input,
+                    seed::spawn_seed_label.run_if(resource_changed::<seed::RoundSeed>()),
+                )
+                    .chain(),
+                (
+                    players::players_button_evt,
+                    players::roster_input,
+                    players::spawn_roster_label.run_if(
+                        resource_changed::<players::Roster>()
+                            .or_else(resource_changed::<Gamepads>()),
+                    ),
+                )
+                    .chain(),
+                lan::spawn_lan_label.run_if(resource_changed::<lan::LanStatus>()),
+            )
+                .run_if(in_state(game::GameState::Menu)),
+        )
+        // In game
+        .add_systems(
+            OnTransition {
+                from: game::GameState::InGame,
+                to: game::GameState::RoundReveal,
+            },
+            score::score_round,
+        )
+        .add_systems(
+            OnTransition {
+                from: game::GameState::RoundReveal,
+                to: game::GameState::InGame,
+            },
+            emoji_ui::rewind_reveal,
+        )
+        .add_systems(
+            Update,
+            (
+                emoji_ui::play_sound_button_evt,
+                emoji_ui::placement_tiles_evt,
+                emoji_ui::emoji_tiles_evt,
+                emoji_ui::submit_btn_evt,
+                emoji_ui::preview_emoji_evt,
+                emoji_ui::page_btn_evt,
+                hint::hint_button_evt,
+                (
+                    party::cursor_input,
+                    party::lock_disconnected,
+                    party::rival_pick_evt,
+                    party::lock_in_evt,
+                )
+                    .chain(),
+            )
+                .run_if(in_state(game::GameState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (emoji_ui::next_btn_evt, emoji_ui::exit_btn_evt).run_if(
+                in_state(game::GameState::InGame).or_else(in_state(game::GameState::RoundReveal)),
+            ),
+        )
+        // Game over
+        .add_systems(
+            OnEnter(game::GameState::GameOver),
+            (
+                game::hide_board,
+                emoji_ui::rewind_reveal,
+                game_over_ui::setup,
+                save::record_game,
+                daily::submit,
+                emoji::stop_audio,
+                hint::despawn_budget_labels,
+                party::despawn_cursors,
+                round_timer::despawn_timer_bar,
+            ),
+        )
+        .add_systems(OnExit(game::GameState::GameOver), game_over_ui::cleanup)
+        .add_systems(
+            OnTransition {
+                from: game::GameState::GameOver,
+                to: game::GameState::InGame,
+            },
+            (
+                game::show_board,
+                game::new_game,
+                score::reset_score,
+                players::reset_scores,
+                seed::reseed,
+            ),
+        )
+        // LAN
+        .add_systems(
+            Update,
+            (
+                lan::client_update,
+                lan::client_send_guess,
+                lan::host_update,
+                lan::host_send_roster.run_if(resource_changed::<players::Roster>()),
+                lan::host_send_round
+                    .run_if(on_event::<emoji::GenerateRandomNumber>())
+                    .after(emoji::generate_random_num),
+                lan::host_send_state.run_if(state_changed::<game::GameState>()),
+            )
+                .chain()
+                .run_if(not(in_state(game::GameState::Loading))),
+        )
+        // Round timer
+        .add_systems(
+            Update,
+            (
+                round_timer::start_timer
+                    .run_if(on_event::<emoji::GenerateRandomNumber>())
+                    .after(emoji_ui::setup),
+                round_timer::tick_timer.run_if(in_state(game::GameState::InGame)),
+            )
+                .chain(),
+        )
+        // Audience
+        .add_systems(
+            Update,
+            (
+                audience::reset_votes.run_if(on_event::<emoji::GenerateRandomNumber>()),
+                audience::accept_votes,
+                audience::score_crowd,
+                audience::spawn_histograms
+                    .run_if(
+                        on_timer(audience::HISTOGRAM_INTERVAL)
+                            .and_then(
+                                resource_changed::<audience::AudienceVotes>()
+                                    .or_else(resource_changed::<emoji_ui::EmojiGuesses>()),
+                            )
+                            .or_else(state_changed::<game::GameState>()),
+                    )
+                    .after(emoji_ui::setup),
+            )
+                .chain()
+                .run_if(not(in_state(game::GameState::Loading))),
+        )
+        .add_systems(
+            Update,
+            game_over_ui::button_evt.run_if(in_state(game::GameState::GameOver)),
+        )
+        // Quitting
+        .add_systems(
+            OnEnter(game::GameState::Quitting),
+            (game::hide_menu, save::flush),
+        )
+        .add_systems(
+            Update,
+            game::exit_when_hidden.run_if(in_state(game::GameState::Quitting)),
+        )
+        // Closing the window skips the quitting state
+        .add_systems(
+            Update,
+            save::flush.run_if(on_event::<WindowCloseRequested>()),
+        )
+        // Paused
+        .add_systems(OnEnter(game::GameState::Paused), game::enter_pause)
+        .add_systems(OnExit(game::GameState::Paused), game::exit_pause)
         .add_systems(
             Update,
             (
                 // board::setup_animation_update,
                 setup_animation_update,
-                menu_ui::start_button_evt,
+                game::clear_input.run_if(state_changed::<game::GameState>()),
                 mouse::mouse_hover,
                 // mouse::hover_animation,
-                game::game_manager,
-            ),
+                game::escape_input,
+            )
+                .chain(),
         )
         .add_systems(
             Update,
             (
-                emoji_ui::play_sound_button_evt,
-                emoji_ui::placement_tiles_evt,
-                emoji_ui::emoji_tiles_evt,
-                emoji::play_audio,
-                emoji_ui::next_btn_evt,
-                emoji_ui::exit_btn_evt,
+                emoji_ui::setup.run_if(resource_changed::<settings::GameSettings>()),
+                (
+                    emoji::stop_audio.run_if(on_event::<emoji::GenerateRandomNumber>()),
+                    emoji::play_audio,
+                    emoji::schedule_audio.run_if(not(in_state(game::GameState::Paused))),
+                )
+                    .chain(),
+                emoji::generate_random_num,
+                (
+                    hint::reset_budget
+                        .run_if(on_event::<emoji::GenerateRandomNumber>())
+                        .after(emoji::generate_random_num),
+                    (hint::hide_removed_tiles, hint::spawn_budget_labels)
+                        .run_if(resource_changed::<hint::HintBudget>()),
+                )
+                    .chain(),
+                (
+                    players::next_turn.run_if(on_event::<emoji::GenerateRandomNumber>()),
+                    players::score_turn,
+                    players::spawn_scoreboard.run_if(
+                        resource_changed::<players::Roster>()
+                            .or_else(state_changed::<game::GameState>()),
+                    ),
+                )
+                    .chain(),
+                (
+                    (party::start_round, party::spawn_cursors)
+                        .chain()
+                        .run_if(on_event::<emoji::GenerateRandomNumber>())
+                        .after(emoji_ui::setup),
+                    party::score_party,
+                    party::place_cursors,
+                    party::spawn_rival_rows.run_if(
+                        resource_changed::<emoji_ui::EmojiGuesses>()
+                            .or_else(resource_changed::<party::PartyRound>())
+                            .or_else(state_changed::<game::GameState>()),
+                    ),
+                )
+                    .chain(),
+                emoji_ui::pulse_playing_tiles,
+                emoji_ui::reveal_answer,
+                emoji_ui::cleanup_reveal,
             ),
         )
-        .add_systems(Update, emoji::generate_random_num)
         .run();
 }
 
-#[derive(Component)]
-pub struct SetupTimeline;
-
 fn setup(mut commands: Commands) {
     // Camera
     commands.spawn(Camera2dBundle::default());
 }
-
-pub fn setup_animation_update(
-    mut q_timelines: Query<&mut Timeline, With<SetupTimeline>>,
-    q_sequences: Query<&Sequence>,
-    time: Res<Time>,
-) {
-    for mut timeline in q_timelines.iter_mut() {
-        let Ok(sequence) = q_sequences.get(timeline.sequence_id().unwrap()) else {
-            continue;
-        };
-
-        // stops updating when timeline reaches the end
-        if (timeline.time_scale > 0.0 && timeline.target_time >= sequence.duration())
-            || (timeline.time_scale < 0.0 && timeline.target_time <= 0.0)
-        {
-            continue;
-        }
-
-        timeline.target_time += timeline.time_scale * time.delta_seconds();
-    }
-}
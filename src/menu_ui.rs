@@ -5,15 +5,34 @@ use bevy_rapier2d::prelude::Collider;
 use motiongfx_typst::TypstCompiler;
 use motiongfx_vello::svg::SvgTreeBundle;
 
-use crate::game::{GameState, GameStateRes};
+use crate::game::GameState;
+use crate::lan::Lan;
+use crate::save::SaveData;
+use crate::settings::{Difficulty, GameSettings};
 use crate::{mouse, SetupTimeline};
 
 #[derive(Component, Default)]
 pub struct StartBtn;
 
+#[derive(Component, Default)]
+pub struct DailyBtn;
+
+#[derive(Component, Default)]
+pub struct PlayersBtn;
+
+#[derive(Component, Default)]
+pub struct DifficultyBtn;
+
+#[derive(Component, Default)]
+pub struct OrderBtn;
+
 #[derive(Component, Default)]
 pub struct QuitBtn;
 
+/// Marker for the difficulty and answer mode text on the main menu.
+#[derive(Component)]
+pub struct OrderLabel;
+
 #[derive(Component)]
 pub struct MenuSetupTimeline;
 
@@ -25,52 +44,230 @@ pub fn menu_button(
     const OFFSET: Vec3 = Vec3::new(500.0, 0.0, 0.0);
     let palette: ColorPalette<ColorKey> = ColorPalette::default();
     let start_color: Color = *palette.get_or_default(&ColorKey::Blue);
+    let daily_color: Color = *palette.get_or_default(&ColorKey::Green);
+    let players_color: Color = *palette.get_or_default(&ColorKey::Yellow);
+    let difficulty_color: Color = *palette.get_or_default(&ColorKey::Orange);
+    let order_color: Color = *palette.get_or_default(&ColorKey::Purple);
     let quit_color: Color = *palette.get_or_default(&ColorKey::Red);
 
     let start_seq: Sequence = create_button::<StartBtn>(
         &mut commands,
         &mut fragments,
         &mut typst_compiler,
-        DVec2::new(200.0, 100.0),
+        DVec2::new(200.0, 80.0),
         100.0,
         start_color,
-        Vec3::new(-500.0, 0.0, 0.0),
+        Vec3::new(-500.0, 230.0, 0.0),
         OFFSET,
         "= Start",
     );
 
+    let daily_seq: Sequence = create_button::<DailyBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 80.0),
+        100.0,
+        daily_color,
+        Vec3::new(-500.0, 138.0, 0.0),
+        OFFSET,
+        "= Daily",
+    );
+
+    let players_seq: Sequence = create_button::<PlayersBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 80.0),
+        100.0,
+        players_color,
+        Vec3::new(-500.0, 46.0, 0.0),
+        OFFSET,
+        "= Players",
+    );
+
+    let difficulty_seq: Sequence = create_button::<DifficultyBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 80.0),
+        100.0,
+        difficulty_color,
+        Vec3::new(-500.0, -46.0, 0.0),
+        OFFSET,
+        "= Level",
+    );
+
+    let order_seq: Sequence = create_button::<OrderBtn>(
+        &mut commands,
+        &mut fragments,
+        &mut typst_compiler,
+        DVec2::new(200.0, 80.0),
+        100.0,
+        order_color,
+        Vec3::new(-500.0, -138.0, 0.0),
+        OFFSET,
+        "= Order",
+    );
+
     let quit_seq: Sequence = create_button::<QuitBtn>(
         &mut commands,
         &mut fragments,
         &mut typst_compiler,
-        DVec2::new(200.0, 100.0),
+        DVec2::new(200.0, 80.0),
         100.0,
         quit_color,
-        Vec3::new(-500.0, -200.0, 0.0),
+        Vec3::new(-500.0, -230.0, 0.0),
         OFFSET,
         "= Quit",
     );
 
-    let sequence: Sequence = flow(0.1, &[start_seq, quit_seq]).with_ease(ease::cubic::ease_in_out);
+    let sequence: Sequence = flow(
+        0.1,
+        &[
+            start_seq,
+            daily_seq,
+            players_seq,
+            difficulty_seq,
+            order_seq,
+            quit_seq,
+        ],
+    )
+    .with_ease(ease::cubic::ease_in_out);
     let sequence_id: Entity = commands.spawn(sequence).id();
 
+    // Revealed once loading is done
     let mut timeline: Timeline = Timeline::new(sequence_id);
-    timeline.time_scale = 1.0;
+    timeline.time_scale = -1.0;
     commands.spawn((timeline, SetupTimeline, MenuSetupTimeline));
 }
 
+/// Starts a game with the settings of the chosen difficulty.
 pub fn start_button_evt(
     q_start_btns: Query<&StartBtn>,
     mut ev_clicked: EventReader<mouse::Clicked>,
-    mut game_state: ResMut<GameStateRes>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut settings: ResMut<GameSettings>,
+    save_data: Res<SaveData>,
+    lan: Res<Lan>,
 ) {
     for clicked in ev_clicked.read() {
+        // LAN clients wait for the host to start
+        if lan.is_client() {
+            continue;
+        }
+
         if let Ok(_) = q_start_btns.get(clicked.entity) {
-            game_state.target_state = GameState::InGame;
+            match settings.difficulty {
+                Difficulty::Custom => save_data.apply_custom(&mut settings),
+                _ => settings.apply_difficulty(),
+            }
+            next_game_state.set(GameState::InGame);
         }
     }
 }
 
+pub fn quit_button_evt(
+    q_quit_btns: Query<&QuitBtn>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for clicked in ev_clicked.read() {
+        if let Ok(_) = q_quit_btns.get(clicked.entity) {
+            next_game_state.set(GameState::Quitting);
+        }
+    }
+}
+
+/// Switches between ordered answers and answers where only the set of emojis matters.
+pub fn order_button_evt(
+    q_order_btns: Query<&OrderBtn>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut settings: ResMut<GameSettings>,
+    lan: Res<Lan>,
+) {
+    for clicked in ev_clicked.read() {
+        // LAN clients play with the settings of the host
+        if lan.is_client() {
+            continue;
+        }
+
+        if let Ok(_) = q_order_btns.get(clicked.entity) {
+            settings.ordered = !settings.ordered;
+        }
+    }
+}
+
+/// Cycles through the difficulty presets.
+pub fn difficulty_button_evt(
+    q_difficulty_btns: Query<&DifficultyBtn>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut settings: ResMut<GameSettings>,
+    save_data: Res<SaveData>,
+    lan: Res<Lan>,
+) {
+    for clicked in ev_clicked.read() {
+        // LAN clients play with the settings of the host
+        if lan.is_client() {
+            continue;
+        }
+
+        if let Ok(_) = q_difficulty_btns.get(clicked.entity) {
+            settings.difficulty = settings.difficulty.next();
+
+            // Otherwise the settings of the last preset would be saved as the custom ones
+            if settings.difficulty == Difficulty::Custom {
+                save_data.apply_custom(&mut settings);
+            }
+        }
+    }
+}
+
+pub fn spawn_order_label(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_order_labels: Query<Entity, With<OrderLabel>>,
+    settings: Res<GameSettings>,
+) {
+    for entity in q_order_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let header: String = r###"
+        #set page(width: 300pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+        #set align(center)
+    "###
+    .into();
+
+    let mode: &str = match settings.ordered {
+        true => "in order",
+        false => "any order",
+    };
+    let text: String = format!("{}, answer {mode}", settings.difficulty.name());
+
+    let Ok(label_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + &text)
+    else {
+        return;
+    };
+
+    commands.entity(label_tree.root_entity).insert((
+        Transform::from_xyz(-label_tree.size.x * 0.5, -283.0, 1.0),
+        OrderLabel,
+    ));
+}
+
+pub fn despawn_order_label(
+    mut commands: Commands,
+    q_order_labels: Query<Entity, With<OrderLabel>>,
+) {
+    for entity in q_order_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 pub fn create_button<Comp: Component + Default>(
     commands: &mut Commands,
     fragments: &mut ResMut<Assets<VelloFragment>>,
@@ -5,11 +5,35 @@ use bevy_rapier2d::prelude::*;
 pub struct Clicked {
     pub entity: Entity,
     pub prev_entity: Option<Entity>,
+    /// Player that clicked, the mouse always belongs to the first player.
+    pub player: usize,
+}
+
+/// Sent when a [`Clickable`] is clicked with the right mouse button.
+#[derive(Event)]
+pub struct RightClicked {
+    pub entity: Entity,
+}
+
+/// Sent when the left mouse button is released over a [`Clickable`].
+#[derive(Event)]
+pub struct Released {
+    pub entity: Entity,
+    /// Entities the button was pressed on, a click when it contains [`Released::entity`] and a drag otherwise.
+    pub pressed: Vec<Entity>,
+}
+
+impl Released {
+    pub fn is_click(&self) -> bool {
+        self.pressed.contains(&self.entity)
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct PreviousClicked {
     pub entity: Option<Entity>,
+    /// Entities under the cursor when the left mouse button was last pressed.
+    pub pressed: Vec<Entity>,
 }
 
 #[derive(Component)]
@@ -22,10 +46,15 @@ pub fn mouse_hover(
     input_mouse: Res<Input<MouseButton>>,
     rapier_context: Res<RapierContext>,
     mut ev_clicked: EventWriter<Clicked>,
+    mut ev_right_clicked: EventWriter<RightClicked>,
+    mut ev_released: EventWriter<Released>,
     mut prev_clicked: ResMut<PreviousClicked>,
 ) {
     // Add Clicked component to hovered entity on clicked
-    if input_mouse.just_pressed(MouseButton::Left) == false {
+    let left: bool = input_mouse.just_pressed(MouseButton::Left);
+    let right: bool = input_mouse.just_pressed(MouseButton::Right);
+    let released: bool = input_mouse.just_released(MouseButton::Left);
+    if left == false && right == false && released == false {
         return;
     }
 
@@ -41,19 +70,41 @@ pub fn mouse_hover(
         .viewport_to_world_2d(camera_transform, cursor_position)
         .unwrap();
 
+    let mut hovered: Vec<Entity> = Vec::new();
     rapier_context.intersections_with_point(cursor_position, QueryFilter::default(), |entity| {
         if q_clickables.contains(entity) {
+            hovered.push(entity);
+        }
+        true
+    });
+
+    for &entity in hovered.iter() {
+        if left {
             ev_clicked.send(Clicked {
                 entity,
                 prev_entity: prev_clicked.entity,
+                player: 0,
             });
 
             prev_clicked.entity = Some(entity);
             println!("Clicking entity {:?}", entity);
         }
-        true
-        // false
-    });
+
+        if right {
+            ev_right_clicked.send(RightClicked { entity });
+        }
+
+        if released {
+            ev_released.send(Released {
+                entity,
+                pressed: prev_clicked.pressed.clone(),
+            });
+        }
+    }
+
+    if left {
+        prev_clicked.pressed = hovered;
+    }
 }
 
 pub fn hover_animation(
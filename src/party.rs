@@ -0,0 +1,515 @@
+use bevy::math::{DVec2, DVec4};
+use bevy::{input::gamepad::GamepadConnectionEvent, prelude::*};
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+
+use crate::emoji::{EmojiMap, RandomNumber};
+use crate::emoji_ui::{self, EmojiGuesses, EmojiMenuBtn, EmojiPage, EmojiTile, RivalGuesses};
+use crate::game::GameState;
+use crate::hint::HintBudget;
+use crate::lan::Lan;
+use crate::mouse;
+use crate::players::{Roster, MAX_PLAYERS, PLAYER_COLORS};
+use crate::score::{RoundResult, RoundScored, SlotResult};
+use crate::settings::GameSettings;
+
+/// Points for locking in first, second, third and last, only given for a guess worth any points.
+pub const SPEED_BONUS: [u32; MAX_PLAYERS] = [50, 30, 15, 0];
+
+/// Device a player guesses with when every player guesses at the same time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    Mouse,
+    /// Arrow keys to move, enter to pick, backspace to undo and space to lock in.
+    Keyboard,
+    /// D-pad to move, south to pick, east to undo and start to lock in.
+    Gamepad(Gamepad),
+    /// Client guessing on another machine, see [`crate::lan`].
+    Remote,
+}
+
+impl InputDevice {
+    fn pressed(
+        &self,
+        action: CursorAction,
+        input_keyboard: &Input<KeyCode>,
+        input_gamepad: &Input<GamepadButton>,
+    ) -> bool {
+        match self {
+            InputDevice::Mouse | InputDevice::Remote => false,
+            InputDevice::Keyboard => input_keyboard.just_pressed(match action {
+                CursorAction::Left => KeyCode::Left,
+                CursorAction::Right => KeyCode::Right,
+                CursorAction::Up => KeyCode::Up,
+                CursorAction::Down => KeyCode::Down,
+                CursorAction::Pick => KeyCode::Return,
+                CursorAction::Undo => KeyCode::Back,
+                CursorAction::Lock => KeyCode::Space,
+            }),
+            InputDevice::Gamepad(gamepad) => input_gamepad.just_pressed(GamepadButton::new(
+                *gamepad,
+                match action {
+                    CursorAction::Left => GamepadButtonType::DPadLeft,
+                    CursorAction::Right => GamepadButtonType::DPadRight,
+                    CursorAction::Up => GamepadButtonType::DPadUp,
+                    CursorAction::Down => GamepadButtonType::DPadDown,
+                    CursorAction::Pick => GamepadButtonType::South,
+                    CursorAction::Undo => GamepadButtonType::East,
+                    CursorAction::Lock => GamepadButtonType::Start,
+                },
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum CursorAction {
+    Left,
+    Right,
+    Up,
+    Down,
+    Pick,
+    Undo,
+    Lock,
+}
+
+/// Players guessing in the current round and the order they locked in.
+#[derive(Resource, Default)]
+pub struct PartyRound {
+    /// Device of every player taking part, by player index.
+    pub devices: Vec<InputDevice>,
+    /// Players in the order they locked in their guesses.
+    pub lock_order: Vec<usize>,
+}
+
+/// Sent when a player is done guessing for the round.
+#[derive(Event)]
+pub struct LockIn {
+    pub player: usize,
+}
+
+/// Highlight over the emoji menu tile a keyboard or gamepad player is pointing at.
+#[derive(Component)]
+pub struct GridCursor {
+    pub player: usize,
+    /// Tile on the current page of the emoji menu.
+    pub slot: usize,
+}
+
+/// Marker for the progress of the players without a placement row on screen.
+#[derive(Component)]
+pub struct RivalRowLabel;
+
+/// Number of players that can guess together on this machine, one per device.
+pub fn local_device_count(gamepads: &Gamepads) -> usize {
+    2 + gamepads.iter().count()
+}
+
+/// Hands out devices and empty rows at the start of every round.
+/// The mouse goes to the first player, the keyboard to the second and gamepads to the rest,
+/// players joining over LAN use their own machine.
+pub fn start_round(
+    mut party_round: ResMut<PartyRound>,
+    mut guesses: ResMut<EmojiGuesses>,
+    roster: Res<Roster>,
+    gamepads: Res<Gamepads>,
+    settings: Res<GameSettings>,
+    lan: Res<Lan>,
+) {
+    party_round.devices.clear();
+    party_round.lock_order.clear();
+    guesses.rivals.clear();
+
+    if roster.simultaneous == false {
+        return;
+    }
+
+    // The host keeps track of the other players
+    if lan.is_client() {
+        party_round.devices = vec![InputDevice::Mouse];
+        return;
+    }
+
+    let mut local_devices = [InputDevice::Mouse, InputDevice::Keyboard]
+        .into_iter()
+        .chain(gamepads.iter().map(InputDevice::Gamepad));
+    party_round.devices = roster
+        .players
+        .iter()
+        .map_while(|player| match player.remote {
+            Some(_) => Some(InputDevice::Remote),
+            None => local_devices.next(),
+        })
+        .collect();
+
+    if party_round.devices.len() < roster.players.len() {
+        warn!(
+            "Only {} of {} players have a device to guess with",
+            party_round.devices.len(),
+            roster.players.len()
+        );
+    }
+
+    // The mouse player uses the placement row
+    for (player, device) in party_round.devices.iter().enumerate() {
+        if *device != InputDevice::Mouse {
+            guesses.rivals.push(RivalGuesses {
+                player,
+                numbers: vec![-1; settings.answer_len()],
+                result: None,
+            });
+        }
+    }
+}
+
+/// Spawns a cursor for every player on a keyboard or gamepad.
+pub fn spawn_cursors(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    q_cursors: Query<Entity, With<GridCursor>>,
+    party_round: Res<PartyRound>,
+    roster: Res<Roster>,
+) {
+    for entity in q_cursors.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+
+    for (player, device) in party_round.devices.iter().enumerate() {
+        if matches!(device, InputDevice::Mouse | InputDevice::Remote) {
+            continue;
+        }
+
+        let color: Color = *palette.get_or_default(&PLAYER_COLORS[roster.players[player].color]);
+        // Nested so several cursors on the same tile stay visible
+        let size: f64 = 50.0 - player.saturating_sub(1) as f64 * 8.0;
+
+        commands.spawn((
+            VelloRectBundle {
+                rect: VelloRect::anchor_center(DVec2::splat(size), DVec4::splat(0.0)),
+                fill: FillStyle::from_brush(Color::NONE),
+                stroke: StrokeStyle::from_brush(color).with_style(4.0),
+                fragment_bundle: VelloFragmentBundle {
+                    fragment: fragments.add(VelloFragment::default()),
+                    ..default()
+                },
+            },
+            GridCursor { player, slot: 0 },
+        ));
+    }
+}
+
+/// Moves the cursors with their devices, picking, undoing and locking in on button presses.
+pub fn cursor_input(
+    mut q_cursors: Query<&mut GridCursor>,
+    q_emoji_tiles: Query<(Entity, &EmojiTile), With<EmojiMenuBtn>>,
+    mut ev_clicked: EventWriter<mouse::Clicked>,
+    mut ev_lock_in: EventWriter<LockIn>,
+    mut guesses: ResMut<EmojiGuesses>,
+    input_keyboard: Res<Input<KeyCode>>,
+    input_gamepad: Res<Input<GamepadButton>>,
+    party_round: Res<PartyRound>,
+    emoji_page: Res<EmojiPage>,
+) {
+    let tile_count: usize = q_emoji_tiles.iter().count();
+    let columns: usize = emoji_page.columns.max(1);
+
+    for mut cursor in q_cursors.iter_mut() {
+        let Some(device) = party_round.devices.get(cursor.player) else {
+            continue;
+        };
+        let pressed =
+            |action: CursorAction| device.pressed(action, &input_keyboard, &input_gamepad);
+
+        // Rows are laid out from the bottom up
+        if pressed(CursorAction::Left) && cursor.slot % columns > 0 {
+            cursor.slot -= 1;
+        }
+        if pressed(CursorAction::Right) && cursor.slot % columns + 1 < columns {
+            cursor.slot += 1;
+        }
+        if pressed(CursorAction::Up) && cursor.slot + columns < tile_count {
+            cursor.slot += columns;
+        }
+        if pressed(CursorAction::Down) && cursor.slot >= columns {
+            cursor.slot -= columns;
+        }
+        let last_slot: usize = tile_count.saturating_sub(1);
+        if cursor.slot > last_slot {
+            cursor.slot = last_slot;
+        }
+
+        if pressed(CursorAction::Pick) {
+            if let Some((entity, _)) = q_emoji_tiles
+                .iter()
+                .find(|(_, emoji_tile)| emoji_tile.index % emoji_ui::PAGE_SIZE == cursor.slot)
+            {
+                ev_clicked.send(mouse::Clicked {
+                    entity,
+                    prev_entity: None,
+                    player: cursor.player,
+                });
+            }
+        }
+
+        // Only borrowed mutably on undo, the rival rows are redrawn whenever the guesses change
+        let Some(rival) = guesses
+            .rivals
+            .iter()
+            .position(|rival| rival.player == cursor.player)
+        else {
+            continue;
+        };
+
+        if pressed(CursorAction::Undo) && party_round.lock_order.contains(&cursor.player) == false {
+            if let Some(last) = guesses.rivals[rival].numbers.iter().rposition(|&n| n != -1) {
+                guesses.rivals[rival].numbers[last] = -1;
+            }
+        }
+
+        if pressed(CursorAction::Lock)
+            && emoji_ui::array_contain_number(&guesses.rivals[rival].numbers, -1) == false
+        {
+            ev_lock_in.send(LockIn {
+                player: cursor.player,
+            });
+        }
+    }
+}
+
+/// Locks in the players whose gamepad was unplugged, so the round does not wait on them.
+pub fn lock_disconnected(
+    mut ev_connection: EventReader<GamepadConnectionEvent>,
+    mut ev_lock_in: EventWriter<LockIn>,
+    party_round: Res<PartyRound>,
+) {
+    for connection in ev_connection.read() {
+        if connection.disconnected() == false {
+            continue;
+        }
+
+        let Some(player) = party_round
+            .devices
+            .iter()
+            .position(|device| *device == InputDevice::Gamepad(connection.gamepad))
+        else {
+            continue;
+        };
+
+        warn!("Gamepad of player {player} was disconnected, locking in their guess");
+        ev_lock_in.send(LockIn { player });
+    }
+}
+
+/// Fills the rows of players clicking with a cursor.
+pub fn rival_pick_evt(
+    q_emoji_tiles: Query<&EmojiTile, With<EmojiMenuBtn>>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut guesses: ResMut<EmojiGuesses>,
+    party_round: Res<PartyRound>,
+    emoji_map: Res<EmojiMap>,
+    hint_budget: Res<HintBudget>,
+) {
+    for clicked in ev_clicked.read() {
+        if clicked.player == 0 || party_round.lock_order.contains(&clicked.player) {
+            continue;
+        }
+
+        let Ok(emoji_tile) = q_emoji_tiles.get(clicked.entity) else {
+            continue;
+        };
+
+        // Tile is hidden on the last page or outside the candidate pool
+        if emoji_tile.index >= emoji_map.data.len()
+            || hint_budget.removed.contains(&emoji_tile.index)
+        {
+            continue;
+        }
+
+        let Some(rival) = guesses
+            .rivals
+            .iter_mut()
+            .find(|rival| rival.player == clicked.player)
+        else {
+            continue;
+        };
+
+        if emoji_ui::array_contain_number(&rival.numbers, emoji_tile.index as i32) {
+            continue;
+        }
+
+        if let Some(slot) = rival.numbers.iter().position(|&n| n == -1) {
+            rival.numbers[slot] = emoji_tile.index as i32;
+        }
+    }
+}
+
+/// Reveals the answer once every player has locked in, LAN clients wait for the host instead.
+pub fn lock_in_evt(
+    mut ev_lock_in: EventReader<LockIn>,
+    mut party_round: ResMut<PartyRound>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    lan: Res<Lan>,
+) {
+    if ev_lock_in.is_empty() {
+        return;
+    }
+
+    for lock_in in ev_lock_in.read() {
+        if lock_in.player < party_round.devices.len()
+            && party_round.lock_order.contains(&lock_in.player) == false
+        {
+            party_round.lock_order.push(lock_in.player);
+        }
+    }
+
+    if party_round.lock_order.len() == party_round.devices.len() && lan.is_client() == false {
+        next_game_state.set(GameState::RoundReveal);
+    }
+}
+
+/// Scores every player on their own guesses, with a bonus for locking in early.
+pub fn score_party(
+    mut ev_round_scored: EventReader<RoundScored>,
+    mut guesses: ResMut<EmojiGuesses>,
+    mut roster: ResMut<Roster>,
+    party_round: Res<PartyRound>,
+    random_number: Res<RandomNumber>,
+    settings: Res<GameSettings>,
+    lan: Res<Lan>,
+) {
+    for round_scored in ev_round_scored.read() {
+        // Clients get their scores from the host
+        if roster.simultaneous == false || lan.is_client() {
+            continue;
+        }
+
+        // Players still guessing when the round timer ran out are scored as well
+        for player in 0..party_round.devices.len() {
+            // The mouse player is the one scored through the placement row,
+            // the speed bonus takes the place of the time bonus
+            let points: u32 = match guesses.rivals.iter_mut().find(|r| r.player == player) {
+                Some(rival) => {
+                    let result: RoundResult =
+                        RoundResult::new(&rival.numbers, &random_number.numbers, settings.ordered);
+                    let points: u32 = result.points;
+                    rival.result = Some(result);
+                    points
+                }
+                None => round_scored.result.points - round_scored.result.time_bonus,
+            };
+
+            let rank: Option<usize> = party_round.lock_order.iter().position(|&p| p == player);
+            let bonus: u32 = match rank {
+                Some(rank) if points > 0 => SPEED_BONUS[rank.min(MAX_PLAYERS - 1)],
+                _ => 0,
+            };
+            roster.players[player].score += points + bonus;
+        }
+    }
+}
+
+/// Keeps every cursor on top of the tile it is pointing at.
+pub fn place_cursors(
+    mut q_cursors: Query<(&GridCursor, &mut Transform)>,
+    q_emoji_tiles: Query<(&EmojiTile, &GlobalTransform), With<EmojiMenuBtn>>,
+) {
+    for (cursor, mut transform) in q_cursors.iter_mut() {
+        if let Some((_, tile_transform)) = q_emoji_tiles
+            .iter()
+            .find(|(emoji_tile, _)| emoji_tile.index % emoji_ui::PAGE_SIZE == cursor.slot)
+        {
+            transform.translation = tile_transform.translation().truncate().extend(3.0);
+        }
+    }
+}
+
+pub fn despawn_cursors(mut commands: Commands, q_cursors: Query<Entity, With<GridCursor>>) {
+    for entity in q_cursors.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Shows how many emojis every other player has picked, and their result once revealed.
+pub fn spawn_rival_rows(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_rival_labels: Query<Entity, With<RivalRowLabel>>,
+    guesses: Res<EmojiGuesses>,
+    party_round: Res<PartyRound>,
+    roster: Res<Roster>,
+    game_state: Res<State<GameState>>,
+) {
+    for entity in q_rival_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if matches!(
+        game_state.get(),
+        GameState::InGame | GameState::RoundReveal | GameState::Paused
+    ) == false
+    {
+        return;
+    }
+
+    let header: String = r###"
+        #set page(width: 220pt, margin: 4pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    for (row, rival) in guesses.rivals.iter().enumerate() {
+        let Some(player) = roster.players.get(rival.player) else {
+            continue;
+        };
+
+        let progress: String = match &rival.result {
+            Some(result) => {
+                let squares: String = result
+                    .slots
+                    .iter()
+                    .map(|slot| match slot {
+                        SlotResult::Exact => '🟩',
+                        SlotResult::Partial => '🟨',
+                        SlotResult::Miss => '⬛',
+                    })
+                    .collect();
+                format!("{squares} +{}", result.points)
+            }
+            None => {
+                let picks: String = rival
+                    .numbers
+                    .iter()
+                    .map(|&n| match n {
+                        -1 => '○',
+                        _ => '●',
+                    })
+                    .collect();
+                match party_round.lock_order.contains(&rival.player) {
+                    true => picks + " 🔒",
+                    false => picks,
+                }
+            }
+        };
+
+        let Ok(label_tree) = typst_compiler.compile_flatten(
+            &mut commands,
+            &mut fragments,
+            header.clone()
+                + &format!(
+                    "#text(fill: {})[*{}*] \\ {progress}",
+                    player.typst_color(),
+                    player.name
+                ),
+        ) else {
+            continue;
+        };
+
+        commands.entity(label_tree.root_entity).insert((
+            Transform::from_xyz(-630.0, 280.0 - row as f32 * 55.0, 1.0),
+            RivalRowLabel,
+        ));
+    }
+}
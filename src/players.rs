@@ -0,0 +1,375 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+use bevy_motiongfx::prelude::*;
+use motiongfx_typst::TypstCompiler;
+
+use crate::emoji_ui::PlayerSelection;
+use crate::game::GameState;
+use crate::lan::Lan;
+use crate::party;
+use crate::score::{RoundScored, Score};
+use crate::{menu_ui, mouse};
+
+/// Largest number of players taking turns on a single device.
+pub const MAX_PLAYERS: usize = 4;
+/// Longest player name that can be typed on the main menu.
+pub const MAX_NAME_LEN: usize = 12;
+/// Colors players can pick from, in the order they are handed out.
+pub const PLAYER_COLORS: [ColorKey; 6] = [
+    ColorKey::Blue,
+    ColorKey::Red,
+    ColorKey::Green,
+    ColorKey::Yellow,
+    ColorKey::Purple,
+    ColorKey::Orange,
+];
+
+#[derive(Clone, Debug)]
+pub struct Player {
+    pub name: String,
+    /// Index into [`PLAYER_COLORS`].
+    pub color: usize,
+    /// Points scored in the current game.
+    pub score: u32,
+    /// Token of the LAN client playing as this player, see [`crate::lan`].
+    pub remote: Option<u64>,
+}
+
+impl Player {
+    /// Player with the default name and color of a seat.
+    pub fn new(index: usize) -> Self {
+        Self {
+            name: format!("Player {}", index + 1),
+            color: index % PLAYER_COLORS.len(),
+            score: 0,
+            remote: None,
+        }
+    }
+
+    /// Color of the player as a Typst `rgb` call.
+    pub fn typst_color(&self) -> String {
+        let palette: ColorPalette<ColorKey> = ColorPalette::default();
+        let [r, g, b, _] = palette
+            .get_or_default(&PLAYER_COLORS[self.color])
+            .as_rgba_u8();
+        format!("rgb({r}, {g}, {b})")
+    }
+}
+
+/// Players taking turns on a single device, one round each,
+/// or guessing at the same time on their own devices.
+#[derive(Resource)]
+pub struct Roster {
+    pub players: Vec<Player>,
+    /// Player whose turn it is.
+    pub current: usize,
+    /// Player being edited on the main menu.
+    pub selected: usize,
+    /// Whether every player guesses at the same time, see [`crate::party`].
+    pub simultaneous: bool,
+}
+
+impl Default for Roster {
+    fn default() -> Self {
+        Self {
+            players: vec![Player::new(0)],
+            current: 0,
+            selected: 0,
+            simultaneous: false,
+        }
+    }
+}
+
+impl Roster {
+    /// Names and colors kept between sessions, players joining over LAN are left out.
+    pub fn entries(&self) -> Vec<(String, usize)> {
+        self.players
+            .iter()
+            .filter(|player| player.remote.is_none())
+            .map(|player| (player.name.clone(), player.color))
+            .collect()
+    }
+
+    pub fn set_entries(&mut self, entries: &[(String, usize)]) {
+        if entries.is_empty() {
+            return;
+        }
+
+        self.players = entries
+            .iter()
+            .take(MAX_PLAYERS)
+            .map(|(name, color)| Player {
+                name: name.clone(),
+                color: color % PLAYER_COLORS.len(),
+                score: 0,
+                remote: None,
+            })
+            .collect();
+        self.current = 0;
+        self.selected = 0;
+    }
+
+    /// Index of the player seated for a LAN client.
+    pub fn seat(&self, token: u64) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.remote == Some(token))
+    }
+
+    pub fn is_multiplayer(&self) -> bool {
+        self.players.len() > 1
+    }
+
+    /// Players from the highest score to the lowest.
+    pub fn leaderboard(&self) -> Vec<&Player> {
+        let mut players: Vec<&Player> = self.players.iter().collect();
+        players.sort_by(|a, b| b.score.cmp(&a.score));
+        players
+    }
+}
+
+/// Marker for the roster on the main menu.
+#[derive(Component)]
+pub struct RosterLabel;
+
+/// Marker for the player scores shown during a game.
+#[derive(Component)]
+pub struct ScoreboardLabel;
+
+/// Adds a player, going back to a single player once the roster is full.
+pub fn players_button_evt(
+    q_players_btns: Query<&menu_ui::PlayersBtn>,
+    mut ev_clicked: EventReader<mouse::Clicked>,
+    mut roster: ResMut<Roster>,
+    lan: Res<Lan>,
+) {
+    for clicked in ev_clicked.read() {
+        // Would remove the players that joined over LAN
+        if lan.is_offline() == false {
+            continue;
+        }
+
+        if let Ok(_) = q_players_btns.get(clicked.entity) {
+            if roster.players.len() < MAX_PLAYERS {
+                let index: usize = roster.players.len();
+                roster.players.push(Player::new(index));
+                roster.selected = index;
+            } else {
+                roster.players.truncate(1);
+                roster.selected = 0;
+            }
+        }
+    }
+}
+
+/// Tab selects the next player, letters rename them, delete removes the last letter,
+/// the up arrow switches to the next color and the right arrow toggles simultaneous guessing.
+pub fn roster_input(
+    mut ev_received_character: EventReader<ReceivedCharacter>,
+    input_keyboard: Res<Input<KeyCode>>,
+    mut roster: ResMut<Roster>,
+    lan: Res<Lan>,
+) {
+    // Players over LAN keep the seats and the mode handed out by the host
+    if lan.is_offline() == false {
+        return;
+    }
+
+    if input_keyboard.just_pressed(KeyCode::Tab) {
+        roster.selected = (roster.selected + 1) % roster.players.len();
+    }
+
+    let selected: usize = roster.selected;
+
+    for received in ev_received_character.read() {
+        if received.char.is_alphabetic() == false && received.char != ' ' {
+            continue;
+        }
+
+        let player: &mut Player = &mut roster.players[selected];
+        // Typing replaces the default name
+        if player.name == Player::new(selected).name {
+            player.name.clear();
+        }
+        if player.name.chars().count() < MAX_NAME_LEN {
+            player.name.push(received.char);
+        }
+    }
+
+    if input_keyboard.just_pressed(KeyCode::Delete) {
+        roster.players[selected].name.pop();
+    }
+
+    if input_keyboard.just_pressed(KeyCode::Up) {
+        let player: &mut Player = &mut roster.players[selected];
+        player.color = (player.color + 1) % PLAYER_COLORS.len();
+    }
+
+    if input_keyboard.just_pressed(KeyCode::Right) {
+        roster.simultaneous = !roster.simultaneous;
+    }
+}
+
+pub fn spawn_roster_label(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_roster_labels: Query<Entity, With<RosterLabel>>,
+    roster: Res<Roster>,
+    gamepads: Res<Gamepads>,
+) {
+    for entity in q_roster_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Guessing together takes a device per player, see `party::start_round`
+    let device_count: usize = match roster.simultaneous {
+        true => party::local_device_count(&gamepads),
+        false => MAX_PLAYERS,
+    };
+
+    let header: String = r###"
+        #set page(width: 260pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+    "###
+    .into();
+
+    let mode: &str = match roster.simultaneous {
+        true => "together",
+        false => "in turns",
+    };
+
+    if let Ok(label_tree) = typst_compiler.compile_flatten(
+        &mut commands,
+        &mut fragments,
+        header.clone() + &format!("*Players* ({mode}) \\ Tab, A-Z, Del, Up, Right"),
+    ) {
+        commands
+            .entity(label_tree.root_entity)
+            .insert((Transform::from_xyz(150.0, 280.0, 1.0), RosterLabel));
+    }
+
+    for (index, player) in roster.players.iter().enumerate() {
+        let cursor: &str = match index == roster.selected {
+            true => "> ",
+            false => "",
+        };
+
+        let no_device: &str = match index >= device_count && player.remote.is_none() {
+            true => " #text(fill: rgb(\"#727072\"))[(no device)]",
+            false => "",
+        };
+
+        let Ok(label_tree) = typst_compiler.compile_flatten(
+            &mut commands,
+            &mut fragments,
+            header.clone()
+                + &format!(
+                    "#text(fill: {})[{cursor}{}]{no_device}",
+                    player.typst_color(),
+                    player.name
+                ),
+        ) else {
+            continue;
+        };
+
+        commands.entity(label_tree.root_entity).insert((
+            Transform::from_xyz(150.0, 220.0 - index as f32 * 30.0, 1.0),
+            RosterLabel,
+            PlayerSelection { player: index },
+        ));
+    }
+}
+
+pub fn despawn_roster_label(
+    mut commands: Commands,
+    q_roster_labels: Query<Entity, With<RosterLabel>>,
+) {
+    for entity in q_roster_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn reset_scores(mut roster: ResMut<Roster>) {
+    for player in roster.players.iter_mut() {
+        player.score = 0;
+    }
+}
+
+/// Hands the board to the next player at the start of every round.
+pub fn next_turn(mut roster: ResMut<Roster>, score: Res<Score>) {
+    roster.current = score.rounds.len() % roster.players.len();
+}
+
+/// Credits the round to the player whose turn it was.
+pub fn score_turn(mut ev_round_scored: EventReader<RoundScored>, mut roster: ResMut<Roster>) {
+    for round_scored in ev_round_scored.read() {
+        // Every player is scored on their own guesses, see `party::score_party`
+        if roster.simultaneous {
+            continue;
+        }
+
+        // Hints bought during the turn are already taken off the points
+        let current: usize = roster.current;
+        roster.players[current].score += round_scored.result.points;
+    }
+}
+
+/// Shows every player's score along the top of the board, highlighting whose turn it is.
+pub fn spawn_scoreboard(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_scoreboard_labels: Query<Entity, With<ScoreboardLabel>>,
+    roster: Res<Roster>,
+    game_state: Res<State<GameState>>,
+) {
+    for entity in q_scoreboard_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if roster.is_multiplayer() == false
+        || matches!(
+            game_state.get(),
+            GameState::InGame | GameState::RoundReveal | GameState::Paused
+        ) == false
+    {
+        return;
+    }
+
+    let header: String = r###"
+        #set page(width: 240pt, margin: 8pt)
+        #set text(size: 18pt, font: "consolas", fill: rgb("#FCFCFA"))
+        #set align(center)
+    "###
+    .into();
+
+    let column_width: f32 = 1280.0 / roster.players.len() as f32;
+
+    for (index, player) in roster.players.iter().enumerate() {
+        let turn: &str = match index == roster.current && roster.simultaneous == false {
+            true => "▶ ",
+            false => "",
+        };
+
+        let Ok(label_tree) = typst_compiler.compile_flatten(
+            &mut commands,
+            &mut fragments,
+            header.clone()
+                + &format!(
+                    "#text(fill: {})[*{turn}{}*] \\ {}",
+                    player.typst_color(),
+                    player.name,
+                    player.score
+                ),
+        ) else {
+            continue;
+        };
+
+        let x: f32 = -640.0 + column_width * (index as f32 + 0.5);
+        commands.entity(label_tree.root_entity).insert((
+            Transform::from_xyz(x - label_tree.size.x * 0.5, 355.0, 1.0),
+            ScoreboardLabel,
+            PlayerSelection { player: index },
+        ));
+    }
+}
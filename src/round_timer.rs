@@ -0,0 +1,140 @@
+use bevy::math::{DVec2, DVec4};
+use bevy::prelude::*;
+use bevy_motiongfx::prelude::*;
+
+use crate::game::GameState;
+use crate::lan::Lan;
+use crate::party::LockIn;
+use crate::settings::GameSettings;
+
+/// Size of the timer bar when the round starts.
+const BAR_SIZE: DVec2 = DVec2::new(800.0, 12.0);
+/// Just above the placement tiles.
+const BAR_POSITION: Vec3 = Vec3::new(0.0, 318.0, 0.0);
+/// Seconds a LAN client runs out of time before the host, so its guess arrives before the reveal.
+const CLIENT_LEAD: f32 = 0.5;
+
+/// Countdown of the current round, only running when [`GameSettings::round_seconds`] is set.
+#[derive(Resource, Default)]
+pub struct RoundTimer(pub Option<Timer>);
+
+impl RoundTimer {
+    /// Share of the round time left, 0 without a timer.
+    pub fn remaining(&self) -> f32 {
+        self.0
+            .as_ref()
+            .map(|timer| timer.percent_left())
+            .unwrap_or(0.0)
+    }
+}
+
+/// Marker for the bar that shrinks as the round time runs out.
+#[derive(Component)]
+pub struct TimerBar;
+
+/// Timeline of the timer bar, following the round time instead of playing on its own.
+#[derive(Component)]
+pub struct TimerBarTimeline;
+
+/// Restarts the countdown and its bar at the start of every round.
+pub fn start_timer(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    q_timer_bars: Query<Entity, With<TimerBar>>,
+    mut round_timer: ResMut<RoundTimer>,
+    settings: Res<GameSettings>,
+    lan: Res<Lan>,
+) {
+    for entity in q_timer_bars.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if settings.round_seconds <= 0.0 {
+        round_timer.0 = None;
+        return;
+    }
+
+    let seconds: f32 = match lan.is_client() {
+        true => f32::max(settings.round_seconds - CLIENT_LEAD, 0.0),
+        false => settings.round_seconds,
+    };
+    round_timer.0 = Some(Timer::from_seconds(seconds, TimerMode::Once));
+
+    // Color palette
+    let palette: ColorPalette<ColorKey> = ColorPalette::default();
+
+    let start_color: Color = *palette.get_or_default(&ColorKey::Green);
+    let half_color: Color = *palette.get_or_default(&ColorKey::Yellow);
+    let end_color: Color = *palette.get_or_default(&ColorKey::Red);
+
+    let rect: VelloRectBundle = VelloRectBundle {
+        rect: VelloRect::anchor_center(BAR_SIZE, DVec4::splat(BAR_SIZE.y * 0.5)),
+        fill: FillStyle::from_brush(start_color),
+        stroke: StrokeStyle::from_brush(Color::NONE).with_style(0.0),
+        fragment_bundle: VelloFragmentBundle {
+            fragment: fragments.add(VelloFragment::default()),
+            transform: TransformBundle::from_transform(Transform::from_translation(BAR_POSITION)),
+            ..default()
+        },
+    };
+
+    let bar_id: Entity = commands.spawn((rect.clone(), TimerBar)).id();
+    let mut rect_motion: VelloRectBundleMotion = VelloRectBundleMotion::new(bar_id, rect);
+
+    // Spans a second, the timeline is moved to the share of time that has passed
+    let mut act: ActionBuilder = ActionBuilder::new(&mut commands);
+    let sequence: Sequence = all(&[
+        act.play(
+            rect_motion.transform.scale_to(Vec3::new(0.0, 1.0, 1.0)),
+            1.0,
+        ),
+        chain(&[
+            act.play(rect_motion.fill.brush_to(half_color), 0.5),
+            act.play(rect_motion.fill.brush_to(end_color), 0.5),
+        ]),
+    ]);
+
+    // Parented to the bar so both go away with it
+    let sequence_id: Entity = commands.spawn(sequence).id();
+    let timeline_id: Entity = commands
+        .spawn((Timeline::new(sequence_id), TimerBarTimeline))
+        .id();
+    commands
+        .entity(bar_id)
+        .push_children(&[sequence_id, timeline_id]);
+}
+
+/// Counts down the round and submits the guesses as they are once time is up.
+pub fn tick_timer(
+    mut q_timelines: Query<&mut Timeline, With<TimerBarTimeline>>,
+    mut round_timer: ResMut<RoundTimer>,
+    mut ev_lock_in: EventWriter<LockIn>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    lan: Res<Lan>,
+    time: Res<Time>,
+) {
+    let Some(timer) = round_timer.0.as_mut() else {
+        return;
+    };
+
+    timer.tick(time.delta());
+    for mut timeline in q_timelines.iter_mut() {
+        timeline.target_time = timer.percent();
+    }
+
+    if timer.just_finished() == false {
+        return;
+    }
+
+    match lan.is_client() {
+        // The host reveals the round, the guess only has to get there
+        true => ev_lock_in.send(LockIn { player: 0 }),
+        false => next_game_state.set(GameState::RoundReveal),
+    }
+}
+
+pub fn despawn_timer_bar(mut commands: Commands, q_timer_bars: Query<Entity, With<TimerBar>>) {
+    for entity in q_timer_bars.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::daily::DailyChallenge;
+use crate::lan::Lan;
+use crate::players::Roster;
+use crate::score::Score;
+use crate::settings::{ClipPlayback, Difficulty, GameSettings};
+
+/// Save file location, relative to the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_PATH: &str = "moji_save.ron";
+
+/// Data kept between sessions.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SaveData {
+    pub best_score: u32,
+    pub games_played: u32,
+    /// Saves from before difficulties were added keep playing with their own settings.
+    #[serde(default = "custom_difficulty")]
+    pub difficulty: Difficulty,
+    pub ordered: bool,
+    // Settings of the custom difficulty
+    pub answer_len: usize,
+    pub rounds_per_game: usize,
+    pub replays_per_round: u32,
+    pub hints_per_round: u32,
+    pub playback: ClipPlayback,
+    pub playback_gap: f32,
+    pub playback_overlap: f32,
+    pub round_seconds: f32,
+    pub pool_size: usize,
+    /// Name and color index of every player in the roster.
+    pub players: Vec<(String, usize)>,
+    /// Date of the last submitted daily challenge, as `YYYY-MM-DD`.
+    pub daily_date: String,
+    /// Share text of the last submitted daily challenge.
+    pub daily_summary: String,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        let settings: GameSettings = GameSettings::default();
+        Self {
+            best_score: 0,
+            games_played: 0,
+            difficulty: settings.difficulty,
+            ordered: settings.ordered,
+            answer_len: settings.answer_len(),
+            rounds_per_game: settings.rounds_per_game,
+            replays_per_round: settings.replays_per_round,
+            hints_per_round: settings.hints_per_round,
+            playback: settings.playback,
+            playback_gap: settings.playback_gap,
+            playback_overlap: settings.playback_overlap,
+            round_seconds: settings.round_seconds,
+            pool_size: settings.pool_size,
+            players: Vec::new(),
+            daily_date: String::new(),
+            daily_summary: String::new(),
+        }
+    }
+}
+
+impl SaveData {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(SAVE_PATH) else {
+            return Self::default();
+        };
+
+        ron::from_str(&text).unwrap_or_else(|err| {
+            warn!("Ignoring invalid save file \"{SAVE_PATH}\": {err}");
+            Self::default()
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self) {
+        let text: String = match ron::ser::to_string_pretty(self, default()) {
+            Ok(text) => text,
+            Err(err) => {
+                error!("Unable to serialize save data: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = std::fs::write(SAVE_PATH, text) {
+            error!("Unable to write save file \"{SAVE_PATH}\": {err}");
+        }
+    }
+
+    // There is no file system to persist to on the web
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn write(&self) {}
+
+    /// Restores the settings of the custom difficulty, kept in a playable range as they can be edited by hand.
+    pub fn apply_custom(&self, settings: &mut GameSettings) {
+        settings.set_answer_len(self.answer_len);
+        settings.rounds_per_game = self.rounds_per_game;
+        settings.replays_per_round = self.replays_per_round;
+        settings.hints_per_round = self.hints_per_round;
+        settings.playback = self.playback;
+        settings.playback_gap = self.playback_gap;
+        settings.playback_overlap = self.playback_overlap;
+        settings.round_seconds = self.round_seconds;
+        settings.pool_size = self.pool_size;
+        settings.clamp_to_limits();
+    }
+}
+
+fn custom_difficulty() -> Difficulty {
+    Difficulty::Custom
+}
+
+/// Applies saved settings on startup.
+pub fn load_save(
+    mut commands: Commands,
+    mut settings: ResMut<GameSettings>,
+    mut roster: ResMut<Roster>,
+) {
+    let save_data: SaveData = SaveData::load();
+
+    save_data.apply_custom(&mut settings);
+    settings.difficulty = save_data.difficulty;
+    settings.ordered = save_data.ordered;
+    roster.set_entries(&save_data.players);
+
+    commands.insert_resource(save_data);
+}
+
+/// Records the result of a finished game.
+pub fn record_game(mut save_data: ResMut<SaveData>, score: Res<Score>) {
+    save_data.games_played += 1;
+    save_data.best_score = u32::max(save_data.best_score, score.total);
+}
+
+/// Writes the current settings and results to disk.
+pub fn flush(
+    mut save_data: ResMut<SaveData>,
+    settings: Res<GameSettings>,
+    roster: Res<Roster>,
+    daily: Res<DailyChallenge>,
+    lan: Res<Lan>,
+) {
+    // Clients play with the settings and roster of the host, only their results are kept
+    if lan.is_client() == false {
+        // The daily challenge plays with its own settings
+        let settings: &GameSettings = daily.player_settings().unwrap_or(&settings);
+
+        save_data.difficulty = settings.difficulty;
+        save_data.ordered = settings.ordered;
+
+        // Presets would overwrite the custom settings
+        if settings.difficulty == Difficulty::Custom {
+            save_data.answer_len = settings.answer_len();
+            save_data.rounds_per_game = settings.rounds_per_game;
+            save_data.replays_per_round = settings.replays_per_round;
+            save_data.hints_per_round = settings.hints_per_round;
+            save_data.playback = settings.playback;
+            save_data.playback_gap = settings.playback_gap;
+            save_data.playback_overlap = settings.playback_overlap;
+            save_data.round_seconds = settings.round_seconds;
+            save_data.pool_size = settings.pool_size;
+        }
+        save_data.players = roster.entries();
+    }
+    save_data.write();
+}
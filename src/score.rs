@@ -0,0 +1,331 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    emoji::RandomNumber, emoji_ui::EmojiGuesses, round_timer::RoundTimer, settings::GameSettings,
+};
+
+/// Points for a guess with the right emoji in the right slot.
+pub const EXACT_POINTS: u32 = 100;
+/// Points for a guess with the right emoji in the wrong slot.
+pub const PARTIAL_POINTS: u32 = 40;
+/// Most points for time left on the round timer, scaled by the share of exact guesses.
+pub const TIME_BONUS_POINTS: u32 = 100;
+
+/// How well a single placement slot matched the answer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SlotResult {
+    /// Right emoji in the right slot.
+    Exact,
+    /// Emoji is in the answer but belongs to another slot.
+    Partial,
+    Miss,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RoundResult {
+    /// Result of each placement slot, in slot order.
+    pub slots: Vec<SlotResult>,
+    /// Answer emoji of each slot, lined up with the guesses when order does not matter.
+    pub answer: Vec<usize>,
+    pub exact: usize,
+    pub partial: usize,
+    pub points: u32,
+    /// Points spent on hints during the round, already taken off [`Self::points`].
+    pub hint_cost: u32,
+    /// Points for time left on the round timer, already part of [`Self::points`].
+    pub time_bonus: u32,
+}
+
+impl RoundResult {
+    /// Compares the guessed emoji indices with the answer, Mastermind style.
+    /// When `ordered` is false any guess that is part of the answer counts as exact.
+    pub fn new(guesses: &[i32], answer: &[usize], ordered: bool) -> Self {
+        if ordered == false {
+            return Self::new(guesses, &Self::align_answer(guesses, answer), true);
+        }
+
+        let mut result: RoundResult = RoundResult {
+            answer: answer.to_vec(),
+            ..default()
+        };
+
+        // Exact guesses first, every answer emoji is only credited once
+        let mut slots: Vec<SlotResult> = vec![SlotResult::Miss; guesses.len()];
+        let mut unmatched: Vec<usize> = Vec::new();
+        for (slot, &index) in answer.iter().enumerate() {
+            if guesses.get(slot) == Some(&(index as i32)) {
+                slots[slot] = SlotResult::Exact;
+            } else {
+                unmatched.push(index);
+            }
+        }
+
+        for (slot, &guess) in guesses.iter().enumerate() {
+            if slots[slot] == SlotResult::Exact || guess < 0 {
+                continue;
+            }
+
+            if let Some(position) = unmatched.iter().position(|&index| index == guess as usize) {
+                unmatched.swap_remove(position);
+                slots[slot] = SlotResult::Partial;
+            }
+        }
+
+        for &slot_result in slots.iter() {
+            match slot_result {
+                SlotResult::Exact => result.exact += 1,
+                SlotResult::Partial => result.partial += 1,
+                SlotResult::Miss => {}
+            }
+        }
+        result.slots = slots;
+
+        result.points = result.exact as u32 * EXACT_POINTS + result.partial as u32 * PARTIAL_POINTS;
+        result
+    }
+
+    /// Reorders the answer so every correctly guessed emoji sits in the slot it was guessed in.
+    fn align_answer(guesses: &[i32], answer: &[usize]) -> Vec<usize> {
+        // Each answer emoji can only be claimed by one guess
+        let mut unclaimed: Vec<usize> = answer.to_vec();
+        let mut aligned: Vec<Option<usize>> = guesses
+            .iter()
+            .map(|&guess| {
+                let position: usize = unclaimed
+                    .iter()
+                    .position(|&index| guess >= 0 && index == guess as usize)?;
+                Some(unclaimed.remove(position))
+            })
+            .collect();
+
+        // Fill the remaining slots with the emojis that were missed
+        let mut missed = unclaimed.into_iter();
+        for slot in aligned.iter_mut().filter(|slot| slot.is_none()) {
+            *slot = missed.next();
+        }
+
+        aligned.into_iter().flatten().collect()
+    }
+
+    /// Adds the bonus for submitting with `remaining` share of the round time left.
+    pub fn add_time_bonus(&mut self, remaining: f32) {
+        if self.slots.is_empty() {
+            return;
+        }
+
+        let accuracy: f32 = self.exact as f32 / self.slots.len() as f32;
+        self.time_bonus = (TIME_BONUS_POINTS as f32 * remaining * accuracy).round() as u32;
+        self.points += self.time_bonus;
+    }
+
+    /// Whether every slot was guessed exactly.
+    pub fn is_perfect(&self) -> bool {
+        self.exact == self.slots.len()
+    }
+}
+
+/// How often an emoji was guessed in the right slot.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct EmojiAccuracy {
+    /// Number of times the emoji was part of an answer.
+    pub seen: u32,
+    /// Number of times it was guessed in the right slot.
+    pub exact: u32,
+}
+
+/// Score accumulated over the current game.
+#[derive(Resource, Default)]
+pub struct Score {
+    pub total: u32,
+    pub rounds: Vec<RoundResult>,
+    /// Number of exact guesses in a row, carried across rounds.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Accuracy keyed by emoji index.
+    pub accuracy: HashMap<usize, EmojiAccuracy>,
+    /// Points spent on hints, already taken off the total.
+    pub hint_cost: u32,
+    /// Points spent on hints since the last round was scored.
+    round_hint_cost: u32,
+}
+
+impl Score {
+    /// Books the cost of a hint, paid out of the points of the round it is bought in.
+    pub fn spend(&mut self, points: u32) {
+        self.round_hint_cost += points;
+    }
+
+    /// Pays the hints of the round out of its points, a round never scores below zero.
+    fn record(&mut self, result: &mut RoundResult) {
+        result.hint_cost = u32::min(std::mem::take(&mut self.round_hint_cost), result.points);
+        result.points -= result.hint_cost;
+        self.hint_cost += result.hint_cost;
+        self.total += result.points;
+
+        for (&slot_result, &emoji) in result.slots.iter().zip(result.answer.iter()) {
+            let accuracy: &mut EmojiAccuracy = self.accuracy.entry(emoji).or_default();
+            accuracy.seen += 1;
+
+            if slot_result == SlotResult::Exact {
+                accuracy.exact += 1;
+                self.streak += 1;
+                self.best_streak = u32::max(self.best_streak, self.streak);
+            } else {
+                self.streak = 0;
+            }
+        }
+
+        self.rounds.push(result.clone());
+    }
+}
+
+/// Sent after a round has been compared against the answer.
+#[derive(Event)]
+pub struct RoundScored {
+    pub result: RoundResult,
+    /// Cumulative score including this round.
+    pub total: u32,
+}
+
+pub fn score_round(
+    guesses: Res<EmojiGuesses>,
+    random_number: Res<RandomNumber>,
+    settings: Res<GameSettings>,
+    round_timer: Res<RoundTimer>,
+    mut score: ResMut<Score>,
+    mut ev_round_scored: EventWriter<RoundScored>,
+) {
+    let mut result: RoundResult =
+        RoundResult::new(&guesses.numbers, &random_number.numbers, settings.ordered);
+    result.add_time_bonus(round_timer.remaining());
+
+    score.record(&mut result);
+    info!(
+        "Round {}: {} exact, {} partial, {} points ({} time bonus, total {})",
+        score.rounds.len(),
+        result.exact,
+        result.partial,
+        result.points,
+        result.time_bonus,
+        score.total
+    );
+
+    ev_round_scored.send(RoundScored {
+        result,
+        total: score.total,
+    });
+}
+
+pub fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_round_scores_exact_partial_and_miss() {
+        let result: RoundResult = RoundResult::new(&[0, 2, 5, -1], &[0, 1, 2, 3], true);
+
+        assert_eq!(
+            result.slots,
+            [
+                SlotResult::Exact,
+                SlotResult::Partial,
+                SlotResult::Miss,
+                SlotResult::Miss
+            ]
+        );
+        assert_eq!(result.exact, 1);
+        assert_eq!(result.partial, 1);
+        assert_eq!(result.points, EXACT_POINTS + PARTIAL_POINTS);
+        assert_eq!(result.answer, [0, 1, 2, 3]);
+        assert!(result.is_perfect() == false);
+    }
+
+    #[test]
+    fn ordered_round_with_swapped_emojis_is_partial() {
+        let result: RoundResult = RoundResult::new(&[1, 0], &[0, 1], true);
+
+        assert_eq!(result.exact, 0);
+        assert_eq!(result.partial, 2);
+        assert_eq!(result.points, PARTIAL_POINTS * 2);
+    }
+
+    #[test]
+    fn unordered_round_counts_any_answer_emoji_as_exact() {
+        let result: RoundResult = RoundResult::new(&[2, 0, 5, -1], &[0, 1, 2, 3], false);
+
+        assert_eq!(
+            result.slots,
+            [
+                SlotResult::Exact,
+                SlotResult::Exact,
+                SlotResult::Miss,
+                SlotResult::Miss
+            ]
+        );
+        assert_eq!(result.partial, 0);
+        assert_eq!(result.points, EXACT_POINTS * 2);
+        // Missed emojis fill the remaining slots in answer order
+        assert_eq!(result.answer, [2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn unordered_perfect_round() {
+        let result: RoundResult = RoundResult::new(&[3, 2, 1, 0], &[0, 1, 2, 3], false);
+
+        assert!(result.is_perfect());
+        assert_eq!(result.points, EXACT_POINTS * 4);
+    }
+
+    #[test]
+    fn duplicate_guesses_are_credited_once() {
+        let result: RoundResult = RoundResult::new(&[5, 5], &[5, 7], false);
+        assert_eq!(result.slots, [SlotResult::Exact, SlotResult::Miss]);
+        assert_eq!(result.answer, [5, 7]);
+        assert_eq!(result.points, EXACT_POINTS);
+
+        let result: RoundResult = RoundResult::new(&[5, 5, 1], &[1, 5, 7], true);
+        assert_eq!(
+            result.slots,
+            [SlotResult::Miss, SlotResult::Exact, SlotResult::Partial]
+        );
+        assert_eq!(result.points, EXACT_POINTS + PARTIAL_POINTS);
+
+        let result: RoundResult = RoundResult::new(&[1, 1, 1], &[7, 8, 1], true);
+        assert_eq!(result.exact, 1);
+        assert_eq!(result.partial, 0);
+    }
+
+    #[test]
+    fn align_answer_keeps_correct_guesses_in_place() {
+        assert_eq!(
+            RoundResult::align_answer(&[-1, 3, 7], &[1, 2, 3]),
+            [1, 3, 2]
+        );
+        assert_eq!(RoundResult::align_answer(&[-1, -1], &[4, 5]), [4, 5]);
+    }
+
+    #[test]
+    fn hints_are_paid_out_of_the_round_points() {
+        let mut score: Score = Score::default();
+
+        // Bought in the first round, before any points were scored
+        score.spend(60);
+        let mut result: RoundResult = RoundResult::new(&[1, 2, 4], &[1, 2, 3], true);
+        score.record(&mut result);
+        assert_eq!(result.hint_cost, 60);
+        assert_eq!(score.total, 2 * EXACT_POINTS - 60);
+
+        // A round never scores below zero
+        score.spend(60);
+        score.spend(20);
+        let mut result: RoundResult = RoundResult::new(&[-1, -1, 5], &[1, 2, 3], true);
+        score.record(&mut result);
+        assert_eq!(result.points, 0);
+        assert_eq!(result.hint_cost, 0);
+        assert_eq!(score.hint_cost, 60);
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+use bevy_motiongfx::prelude::*;
+use bevy_prng::ChaCha8Rng;
+use bevy_rand::prelude::GlobalEntropy;
+use motiongfx_typst::TypstCompiler;
+
+use crate::daily::DailyChallenge;
+
+/// Seed used to generate the rounds of a game.
+#[derive(Resource, Default)]
+pub struct RoundSeed {
+    /// Seed chosen from the command line or the menu, a random seed is used for every game otherwise.
+    pub fixed: Option<u64>,
+    /// Seed of the current game.
+    pub current: u64,
+}
+
+impl RoundSeed {
+    /// Reads `--seed <number>` from the command line.
+    pub fn from_args() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--seed").skip(1);
+
+        let fixed: Option<u64> = args.next().and_then(|seed| match seed.parse::<u64>() {
+            Ok(seed) => Some(seed),
+            Err(err) => {
+                warn!("Ignoring invalid seed \"{seed}\": {err}");
+                None
+            }
+        });
+
+        Self { fixed, current: 0 }
+    }
+}
+
+/// Marker for the seed text on the main menu.
+#[derive(Component)]
+pub struct SeedLabel;
+
+/// Expands a 64 bit seed into a full [`ChaCha8Rng`] seed.
+pub fn seed_bytes(seed: u64) -> [u8; 32] {
+    let mut bytes: [u8; 32] = [0; 32];
+    for (i, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+        chunk.copy_from_slice(&seed.wrapping_add(i as u64).to_le_bytes());
+    }
+    bytes
+}
+
+/// Reseeds the global rng at the start of a game so its rounds can be reproduced.
+pub fn reseed(
+    mut round_seed: ResMut<RoundSeed>,
+    mut rng: ResMut<GlobalEntropy<ChaCha8Rng>>,
+    daily: Res<DailyChallenge>,
+) {
+    round_seed.current = match daily.active {
+        true => daily.seed(),
+        false => round_seed.fixed.unwrap_or_else(rand::random),
+    };
+    rng.reseed(seed_bytes(round_seed.current));
+
+    info!("Starting game with seed {}", round_seed.current);
+}
+
+/// Lets the player type a seed on the main menu, backspace removes the last digit.
+pub fn seed_input(
+    mut ev_received_character: EventReader<ReceivedCharacter>,
+    input_keyboard: Res<Input<KeyCode>>,
+    mut round_seed: ResMut<RoundSeed>,
+) {
+    for received in ev_received_character.read() {
+        let Some(digit) = received.char.to_digit(10) else {
+            continue;
+        };
+
+        let seed: Option<u64> = round_seed
+            .fixed
+            .unwrap_or(0)
+            .checked_mul(10)
+            .and_then(|seed| seed.checked_add(digit as u64));
+
+        if seed.is_some() {
+            round_seed.fixed = seed;
+        }
+    }
+
+    if input_keyboard.just_pressed(KeyCode::Back) {
+        round_seed.fixed = round_seed.fixed.and_then(|seed| match seed / 10 {
+            0 => None,
+            seed => Some(seed),
+        });
+    }
+}
+
+pub fn spawn_seed_label(
+    mut commands: Commands,
+    mut fragments: ResMut<Assets<VelloFragment>>,
+    mut typst_compiler: ResMut<TypstCompiler>,
+    q_seed_labels: Query<Entity, With<SeedLabel>>,
+    round_seed: Res<RoundSeed>,
+) {
+    for entity in q_seed_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let header: String = r###"
+        #set page(width: 300pt, margin: 8pt)
+        #set text(size: 16pt, font: "consolas", fill: rgb("#FCFCFA"))
+        #set align(center)
+    "###
+    .into();
+
+    let seed: String = match round_seed.fixed {
+        Some(seed) => format!("Seed: {seed}"),
+        None => "Seed: random (type to set)".into(),
+    };
+
+    let Ok(label_tree) =
+        typst_compiler.compile_flatten(&mut commands, &mut fragments, header + &seed)
+    else {
+        return;
+    };
+
+    commands.entity(label_tree.root_entity).insert((
+        Transform::from_xyz(-label_tree.size.x * 0.5, -320.0, 1.0),
+        SeedLabel,
+    ));
+}
+
+pub fn despawn_seed_label(mut commands: Commands, q_seed_labels: Query<Entity, With<SeedLabel>>) {
+    for entity in q_seed_labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::{app::AppExit, input::InputPlugin, prelude::*};
+use bevy_prng::ChaCha8Rng;
+use bevy_rand::prelude::EntropyPlugin;
+
+use crate::emoji::{self, EmojiMap, RandomNumber};
+use crate::emoji_ui::{self, EmojiGuesses};
+use crate::game::{self, GameState};
+use crate::lan::{self, Lan};
+use crate::players::{self, Roster};
+use crate::score::{self, Score};
+use crate::settings::{self, GameSettings};
+use crate::{daily, party, round_timer, seed};
+
+/// Seconds the lobby waits for more players once someone joined.
+pub const LOBBY_SECONDS: f32 = 10.0;
+/// Longest a round can take before the answer is revealed, unless [`GameSettings::round_seconds`] is set.
+pub const ROUND_SECONDS: f32 = 90.0;
+/// Seconds the answer stays revealed before the next round.
+pub const REVEAL_SECONDS: f32 = 8.0;
+/// Seconds the results stay up before going back to the lobby.
+pub const GAME_OVER_SECONDS: f32 = 10.0;
+
+/// Time left in the current screen of a headless game, which nobody is there to click through.
+#[derive(Resource, Default)]
+pub struct PhaseTimer(pub Timer);
+
+/// Adds the round logic of a headless host to an app that already has the minimal plugins.
+pub fn build(app: &mut App, lan: Lan) {
+    app.add_plugins(InputPlugin)
+        .add_plugins(EntropyPlugin::<ChaCha8Rng>::default())
+        // States
+        .add_state::<game::GameState>()
+        // Resources
+        .insert_resource(lan)
+        .insert_resource(lan::LanStatus::default())
+        .insert_resource(emoji::EmojiMap::default())
+        .insert_resource(emoji::RandomNumber::default())
+        .insert_resource(settings::GameSettings::default())
+        .insert_resource(score::Score::default())
+        .insert_resource(seed::RoundSeed::from_args())
+        .insert_resource(daily::DailyChallenge::default())
+        .insert_resource(emoji_ui::EmojiGuesses::default())
+        .insert_resource(party::PartyRound::default())
+        .insert_resource(PhaseTimer::default())
+        .insert_resource(round_timer::RoundTimer::default())
+        // Every player joins over LAN
+        .insert_resource(players::Roster {
+            players: Vec::new(),
+            current: 0,
+            selected: 0,
+            simultaneous: true,
+        })
+        .add_event::<emoji::GenerateRandomNumber>()
+        .add_event::<score::RoundScored>()
+        .add_event::<party::LockIn>()
+        // Systems
+        .add_systems(Startup, setup)
+        .add_systems(
+            OnTransition {
+                from: game::GameState::Menu,
+                to: game::GameState::InGame,
+            },
+            (
+                game::new_game,
+                score::reset_score,
+                players::reset_scores,
+                seed::reseed,
+            ),
+        )
+        .add_systems(
+            OnTransition {
+                from: game::GameState::InGame,
+                to: game::GameState::RoundReveal,
+            },
+            score::score_round,
+        )
+        .add_systems(OnEnter(game::GameState::GameOver), log_results)
+        .add_systems(
+            Update,
+            (
+                reset_timer.run_if(state_changed::<game::GameState>()),
+                advance,
+                emoji::generate_random_num,
+                party::start_round.run_if(on_event::<emoji::GenerateRandomNumber>()),
+                lan::host_update,
+                party::lock_in_evt.run_if(in_state(game::GameState::InGame)),
+                party::score_party,
+                lan::host_send_roster.run_if(resource_changed::<players::Roster>()),
+                lan::host_send_round.run_if(on_event::<emoji::GenerateRandomNumber>()),
+                lan::host_send_state.run_if(state_changed::<game::GameState>()),
+            )
+                .chain(),
+        );
+}
+
+/// Always hosts, on [`lan::DEFAULT_PORT`] unless `--host <port>` says otherwise.
+pub fn host_from_args() -> Lan {
+    match Lan::from_args() {
+        Lan::Host(host) => Lan::Host(host),
+        _ => Lan::host(lan::DEFAULT_PORT),
+    }
+}
+
+/// Loads the emoji ids from disk and sizes the answer, then opens the lobby.
+pub fn setup(
+    mut ev_app_exit: EventWriter<AppExit>,
+    lan: Res<Lan>,
+    mut emoji_map: ResMut<EmojiMap>,
+    mut random_number: ResMut<RandomNumber>,
+    mut guesses: ResMut<EmojiGuesses>,
+    settings: Res<GameSettings>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let Lan::Host(host) = lan.as_ref() else {
+        // Unable to host, the reason was already logged
+        ev_app_exit.send(AppExit);
+        return;
+    };
+    info!("Lobby {} open on port {}", host.code, host.port);
+
+    match emoji::read_manifest() {
+        Ok(manifest) => {
+            emoji_map.names = manifest.emojis.into_iter().map(|emoji| emoji.id).collect();
+            info!("Loaded {} emojis", emoji_map.names.len());
+        }
+        Err(err) => {
+            // Rounds cannot be played without emojis
+            error!("{err}");
+            ev_app_exit.send(AppExit);
+            return;
+        }
+    }
+
+    random_number.numbers = vec![0; settings.answer_len()];
+    guesses.numbers = vec![-1; settings.answer_len()];
+    next_game_state.set(GameState::Menu);
+}
+
+pub fn reset_timer(
+    mut phase_timer: ResMut<PhaseTimer>,
+    game_state: Res<State<GameState>>,
+    settings: Res<GameSettings>,
+) {
+    let seconds: f32 = match game_state.get() {
+        GameState::Menu => LOBBY_SECONDS,
+        GameState::InGame if settings.round_seconds > 0.0 => settings.round_seconds,
+        GameState::InGame => ROUND_SECONDS,
+        GameState::RoundReveal => REVEAL_SECONDS,
+        GameState::GameOver => GAME_OVER_SECONDS,
+        _ => return,
+    };
+
+    phase_timer.0 = Timer::from_seconds(seconds, TimerMode::Once);
+}
+
+/// Moves on to the next screen once its time is up.
+pub fn advance(
+    mut phase_timer: ResMut<PhaseTimer>,
+    mut ev_gen_rand_num: EventWriter<emoji::GenerateRandomNumber>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+    roster: Res<Roster>,
+    score: Res<Score>,
+    settings: Res<GameSettings>,
+    time: Res<Time>,
+) {
+    // The lobby only counts down while someone is waiting
+    if *game_state.get() == GameState::Menu && (roster.players.is_empty() || roster.is_changed()) {
+        phase_timer.0.reset();
+        return;
+    }
+
+    if phase_timer.0.tick(time.delta()).just_finished() == false {
+        return;
+    }
+
+    match game_state.get() {
+        GameState::Menu => next_game_state.set(GameState::InGame),
+        GameState::InGame => next_game_state.set(GameState::RoundReveal),
+        GameState::RoundReveal if score.rounds.len() >= settings.rounds_per_game => {
+            next_game_state.set(GameState::GameOver)
+        }
+        GameState::RoundReveal => {
+            ev_gen_rand_num.send(emoji::GenerateRandomNumber);
+            next_game_state.set(GameState::InGame);
+        }
+        GameState::GameOver => next_game_state.set(GameState::Menu),
+        _ => {}
+    }
+}
+
+/// Logs the final standings, the only place they show up without a screen.
+pub fn log_results(roster: Res<Roster>) {
+    for (rank, player) in roster.leaderboard().iter().enumerate() {
+        info!("#{} {} with {} points", rank + 1, player.name, player.score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::party::{LockIn, PartyRound};
+    use crate::players::Player;
+
+    /// Headless host on a free port, every update moving the clock a second ahead.
+    fn server_app(rounds_per_game: usize) -> App {
+        let mut app: App = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1)));
+        build(&mut app, Lan::host(0));
+        app.world
+            .resource_mut::<Time<Virtual>>()
+            .set_max_delta(Duration::from_secs(1));
+
+        app.world.resource_mut::<GameSettings>().rounds_per_game = rounds_per_game;
+        // Stands in for a client that already joined
+        app.world.resource_mut::<Roster>().players.push(Player {
+            remote: Some(1),
+            ..Player::new(0)
+        });
+        app
+    }
+
+    fn game_state(app: &App) -> GameState {
+        *app.world.resource::<State<GameState>>().get()
+    }
+
+    /// Updates the app until it gets to `state`, failing after `max_updates`.
+    fn update_until(app: &mut App, state: GameState, max_updates: usize) {
+        for _ in 0..max_updates {
+            app.update();
+            if game_state(app) == state {
+                return;
+            }
+        }
+
+        panic!(
+            "Stuck in {:?} instead of reaching {state:?}",
+            game_state(app)
+        );
+    }
+
+    #[test]
+    fn plays_a_game_from_the_lobby_to_the_results() {
+        let mut app: App = server_app(2);
+
+        update_until(&mut app, GameState::Menu, 3);
+        update_until(&mut app, GameState::InGame, LOBBY_SECONDS as usize + 3);
+
+        for round in 1..=2 {
+            let answer_len: usize = app.world.resource::<GameSettings>().answer_len();
+            assert_eq!(
+                app.world.resource::<RandomNumber>().numbers.len(),
+                answer_len
+            );
+            assert_eq!(app.world.resource::<PartyRound>().devices.len(), 1);
+
+            // Everyone locked in, so the answer is revealed without waiting for the clock
+            app.world.send_event(LockIn { player: 0 });
+            update_until(&mut app, GameState::RoundReveal, 2);
+            assert_eq!(app.world.resource::<Score>().rounds.len(), round);
+
+            if round < 2 {
+                update_until(&mut app, GameState::InGame, REVEAL_SECONDS as usize + 3);
+            }
+        }
+
+        update_until(&mut app, GameState::GameOver, REVEAL_SECONDS as usize + 3);
+        update_until(&mut app, GameState::Menu, GAME_OVER_SECONDS as usize + 3);
+        assert_eq!(app.world.resource::<Roster>().players.len(), 1);
+    }
+
+    #[test]
+    fn round_is_revealed_when_time_runs_out() {
+        let mut app: App = server_app(1);
+
+        update_until(&mut app, GameState::InGame, LOBBY_SECONDS as usize + 5);
+        update_until(&mut app, GameState::RoundReveal, ROUND_SECONDS as usize + 3);
+        assert_eq!(app.world.resource::<Score>().rounds.len(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Smallest number of emojis in a single round.
+pub const MIN_ANSWER_LEN: usize = 2;
+/// Largest number of emojis in a single round.
+pub const MAX_ANSWER_LEN: usize = 8;
+/// Longest gap or overlap between two clips.
+const MAX_PLAYBACK_SECONDS: f32 = 5.0;
+/// Longest round, larger values would not fit in the round timer.
+const MAX_ROUND_SECONDS: f32 = 600.0;
+
+/// How the clips of a round are played back, from easiest to hardest.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClipPlayback {
+    /// One clip after another in answer order, separated by [`GameSettings::playback_gap`].
+    Sequential,
+    /// In answer order, each clip starting [`GameSettings::playback_overlap`] seconds before the previous one ends.
+    Overlapped,
+    /// Every clip at once.
+    #[default]
+    Simultaneous,
+}
+
+/// Preset applied to the settings when a game is started from the main menu.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// Keeps the settings stored in the save file.
+    Custom,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Custom,
+            Difficulty::Custom => Difficulty::Easy,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Custom => "Custom",
+        }
+    }
+}
+
+/// Settings that shape a round, changeable at runtime.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GameSettings {
+    /// Number of emojis the player needs to guess each round.
+    answer_len: usize,
+    /// Number of rounds before the game is over.
+    pub rounds_per_game: usize,
+    /// Whether the emojis have to be placed in the order they are played, otherwise only the set matters.
+    pub ordered: bool,
+    /// Number of times the answer can be played each round.
+    pub replays_per_round: u32,
+    /// Number of hints that can be bought each round.
+    pub hints_per_round: u32,
+    pub playback: ClipPlayback,
+    /// Seconds of silence between clips in [`ClipPlayback::Sequential`].
+    pub playback_gap: f32,
+    /// Seconds that clips overlap in [`ClipPlayback::Overlapped`].
+    pub playback_overlap: f32,
+    /// Seconds to guess each round before the guesses are submitted as they are, no limit when 0.
+    pub round_seconds: f32,
+    /// Number of emojis shown on the emoji menu each round, answer included, every emoji when 0.
+    pub pool_size: usize,
+    pub difficulty: Difficulty,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            answer_len: 4,
+            rounds_per_game: 5,
+            ordered: true,
+            replays_per_round: 3,
+            hints_per_round: 2,
+            playback: ClipPlayback::default(),
+            playback_gap: 0.5,
+            playback_overlap: 0.5,
+            round_seconds: 0.0,
+            pool_size: 0,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+impl GameSettings {
+    pub fn answer_len(&self) -> usize {
+        self.answer_len
+    }
+
+    /// Sets the answer length, clamped to [`MIN_ANSWER_LEN`]..=[`MAX_ANSWER_LEN`].
+    pub fn set_answer_len(&mut self, answer_len: usize) {
+        self.answer_len = answer_len.clamp(MIN_ANSWER_LEN, MAX_ANSWER_LEN);
+    }
+
+    /// Brings settings read from a save or sent by a LAN host back within what the game can play.
+    pub fn clamp_to_limits(&mut self) {
+        let defaults: GameSettings = GameSettings::default();
+
+        self.set_answer_len(self.answer_len);
+        self.rounds_per_game = self.rounds_per_game.max(1);
+        self.playback_gap = clamp_seconds(
+            self.playback_gap,
+            MAX_PLAYBACK_SECONDS,
+            defaults.playback_gap,
+        );
+        self.playback_overlap = clamp_seconds(
+            self.playback_overlap,
+            MAX_PLAYBACK_SECONDS,
+            defaults.playback_overlap,
+        );
+        self.round_seconds = clamp_seconds(
+            self.round_seconds,
+            MAX_ROUND_SECONDS,
+            defaults.round_seconds,
+        );
+    }
+
+    /// Reshapes the round after a preset, [`Difficulty::Custom`] is left as it is.
+    pub fn apply_difficulty(&mut self) {
+        let (answer_len, pool_size, playback, replays_per_round, round_seconds) =
+            match self.difficulty {
+                Difficulty::Easy => (3, 9, ClipPlayback::Sequential, 5, 0.0),
+                Difficulty::Normal => (4, 16, ClipPlayback::Overlapped, 3, 90.0),
+                Difficulty::Hard => (6, 0, ClipPlayback::Simultaneous, 1, 45.0),
+                Difficulty::Custom => return,
+            };
+
+        self.set_answer_len(answer_len);
+        self.pool_size = pool_size;
+        self.playback = playback;
+        self.replays_per_round = replays_per_round;
+        self.round_seconds = round_seconds;
+    }
+}
+
+/// Clamps seconds to `0.0..=max`, falling back to `default` for values that are not a number.
+fn clamp_seconds(seconds: f32, max: f32, default: f32) -> f32 {
+    match seconds.is_nan() {
+        true => default,
+        false => seconds.clamp(0.0, max),
+    }
+}